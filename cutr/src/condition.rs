@@ -0,0 +1,153 @@
+use crate::CutrResult;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::error::Error;
+use std::sync::OnceLock;
+
+/// A `--where` test on one field of a row, like `col3 > 100`,
+/// `name ~ /regex/` or `status == "ok"`
+#[derive(Debug, Clone)]
+pub struct Condition {
+    column: Column,
+    op: Op,
+    value: Value,
+}
+
+/// The field a condition tests
+#[derive(Debug, Clone)]
+enum Column {
+    /// `colN`, 0-based
+    Index(usize),
+    /// Looked up in the header
+    Name(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Match,
+    NotMatch,
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(f64),
+    Text(String),
+    Regex(Regex),
+}
+
+impl Condition {
+    pub(crate) fn parse(expr: &str) -> Result<Self, Box<dyn Error + Send + Sync + 'static>> {
+        let expr_re = Regex::new(r"^\s*(.+?)\s*(==|!=|<=|>=|!~|<|>|~)\s*(.*?)\s*$").unwrap();
+        let col_re = Regex::new(r"^col(\d+)$").unwrap();
+        let invalid = || {
+            format!(
+                "invalid --where expression '{}', expected one like 'col3 > 100', \
+                'name ~ /regex/' or 'status == \"ok\"'",
+                expr
+            )
+        };
+
+        let captures = expr_re.captures(expr).ok_or_else(invalid)?;
+        let (column, op, value) = (&captures[1], &captures[2], &captures[3]);
+        let column = match col_re.captures(column) {
+            Some(captures) => match captures[1].parse::<usize>() {
+                Ok(n) if n > 0 => Column::Index(n - 1),
+                _ => return Err(invalid().into()),
+            },
+            None => Column::Name(column.to_string()),
+        };
+        let op = match op {
+            "==" => Op::Eq,
+            "!=" => Op::Ne,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "~" => Op::Match,
+            _ => Op::NotMatch,
+        };
+
+        let quoted = |quote: char| {
+            value
+                .strip_prefix(quote)
+                .and_then(|value| value.strip_suffix(quote))
+                .filter(|_| value.len() > 1)
+        };
+        let value = match op {
+            Op::Match | Op::NotMatch => Value::Regex(Regex::new(quoted('/').unwrap_or(value))?),
+            _ => match quoted('"') {
+                Some(text) => Value::Text(text.to_string()),
+                None => parse_number(value)
+                    .map_or_else(|| Value::Text(value.to_string()), Value::Number),
+            },
+        };
+
+        Ok(Condition { column, op, value })
+    }
+
+    /// The index of the tested field, with names looked up in `headers`
+    pub(crate) fn index(&self, headers: Option<&[String]>) -> CutrResult<usize> {
+        match (&self.column, headers) {
+            (Column::Index(i), _) => Ok(*i),
+            (Column::Name(name), Some(headers)) => headers
+                .iter()
+                .position(|header| header == name)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown field name: {} (the headers are: {})",
+                        name,
+                        headers.join(", ")
+                    )
+                    .into()
+                }),
+            (Column::Name(name), None) => {
+                Err(format!("--where on field name {} needs --header, or use colN", name).into())
+            }
+        }
+    }
+
+    /// Whether `field` passes. A field that is missing, or isn't a number
+    /// when compared with one, only passes `!=` and `!~`.
+    pub(crate) fn is_match(&self, field: Option<&str>) -> bool {
+        let Some(field) = field else {
+            return matches!(self.op, Op::Ne | Op::NotMatch);
+        };
+        let ordering = match &self.value {
+            Value::Regex(regex) => return regex.is_match(field) == (self.op == Op::Match),
+            Value::Number(number) => {
+                parse_number(field.trim()).and_then(|field| field.partial_cmp(number))
+            }
+            Value::Text(text) => Some(field.cmp(text.as_str())),
+        };
+
+        match ordering {
+            None => self.op == Op::Ne,
+            Some(ordering) => match self.op {
+                Op::Eq => ordering == Ordering::Equal,
+                Op::Ne => ordering != Ordering::Equal,
+                Op::Lt => ordering == Ordering::Less,
+                Op::Le => ordering != Ordering::Greater,
+                Op::Gt => ordering == Ordering::Greater,
+                Op::Ge => ordering != Ordering::Less,
+                Op::Match | Op::NotMatch => unreachable!("matched with a regex"),
+            },
+        }
+    }
+}
+
+/// `text` as a number if it is written as a decimal one, like `-1.5` or
+/// `1e3`. Words that `f64` also parses, like `nan` and `inf`, stay text.
+fn parse_number(text: &str) -> Option<f64> {
+    static NUMBER_RE: OnceLock<Regex> = OnceLock::new();
+    NUMBER_RE
+        .get_or_init(|| Regex::new(r"^[+-]?(\d+\.?\d*|\.\d+)([eE][+-]?\d+)?$").unwrap())
+        .is_match(text)
+        .then(|| text.parse().ok())
+        .flatten()
+}
@@ -1,133 +1,656 @@
 use clap::{ArgGroup, Parser};
-use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use csv::{ByteRecord, ReaderBuilder, StringRecord};
+use output::{FieldWriter, Row};
 use regex::Regex;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Write};
 use std::num::NonZeroUsize;
 use std::{error::Error, ops::Range};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
+mod condition;
+mod output;
+
+pub use condition::Condition;
+pub use output::OutputFormat;
+
+/// Selected 0-based positions, in the order given. An open-ended range like
+/// `3-` ends at `usize::MAX` and is cut down to the length of each line.
+/// Unless `--reorder` is given the list is sorted and merged, so positions
+/// come out in input order and once each, like POSIX cut.
 type PositionList = Vec<Range<usize>>;
 
 #[derive(Debug, Parser)]
-#[command(group(ArgGroup::new("pos").required(true).args(["fields", "chars", "bytes"])))]
+#[command(group(ArgGroup::new("pos").args(["fields", "field_names", "chars", "bytes"])))]
 pub struct Opts {
-    #[arg(short, long, help = "Selected fields", value_name = "FIELDS", value_parser = parse_pos)]
+    #[arg(short, long, help = "Selected fields", value_name = "FIELDS", allow_hyphen_values = true, value_parser = parse_pos)]
     fields: Option<PositionList>,
 
-    #[arg(short, long, help = "Selected bytes", value_name = "BYTES", conflicts_with_all = ["fields", "chars"], value_parser = parse_pos)]
+    #[arg(short = 'F', long, help = "Selected fields by header name, `*` and `?` match any text or char", value_name = "NAMES", requires = "header", conflicts_with_all = ["fields", "bytes", "chars"], value_delimiter = ',')]
+    field_names: Option<Vec<String>>,
+
+    #[arg(short, long, help = "Selected bytes", value_name = "BYTES", conflicts_with_all = ["fields", "chars"], allow_hyphen_values = true, value_parser = parse_pos)]
     bytes: Option<PositionList>,
 
-    #[arg(short, long, help = "Selected chars", value_name = "CHARS", conflicts_with_all = ["fields", "bytes"], value_parser = parse_pos)]
+    #[arg(short, long, help = "Selected chars", value_name = "CHARS", conflicts_with_all = ["fields", "bytes"], allow_hyphen_values = true, value_parser = parse_pos)]
     chars: Option<PositionList>,
 
-    #[arg(short, long, help = "Field delimiter",value_parser = parse_delim, default_value = "\t")]
-    delimiter: u8,
+    #[arg(
+        long,
+        help = "Count --chars in grapheme clusters, so accents and emoji stay whole",
+        requires = "chars",
+        conflicts_with_all = ["fields", "field_names", "bytes", "display_width"]
+    )]
+    graphemes: bool,
+
+    #[arg(
+        long,
+        help = "Count --chars in terminal columns, two for wide characters",
+        requires = "chars",
+        conflicts_with_all = ["fields", "field_names", "bytes"]
+    )]
+    display_width: bool,
+
+    #[arg(
+        short = 'n',
+        long,
+        help = "Never split a character with --bytes: a range starting inside one takes it in, a range ending inside one stops before it",
+        requires = "bytes",
+        conflicts_with_all = ["fields", "field_names", "chars"]
+    )]
+    whole_chars: bool,
+
+    #[arg(
+        long,
+        help = "Print the selected --bytes exactly, even if they aren't valid UTF-8",
+        requires = "bytes",
+        conflicts_with_all = ["fields", "field_names", "chars"]
+    )]
+    raw_bytes: bool,
+
+    #[arg(short, long, help = "Field delimiter", value_parser = parse_delim, default_value = "\t")]
+    delimiter: String,
+
+    #[arg(long, help = "Field delimiter regex, like `\\s+` for aligned columns. A match at the start of a line is skipped", value_name = "REGEX", conflicts_with = "delimiter", value_parser = Regex::new)]
+    regex_delimiter: Option<Regex>,
+
+    #[arg(
+        long,
+        help = "Delimiter between printed fields [default: the field delimiter, or a tab with --regex-delimiter]",
+        value_name = "STRING"
+    )]
+    output_delimiter: Option<String>,
+
+    #[arg(
+        long,
+        help = "Print the fields in this format, keyed by the --header for JSON",
+        value_name = "FORMAT",
+        conflicts_with_all = ["output_delimiter", "bytes", "chars"]
+    )]
+    output_format: Option<OutputFormat>,
+
+    #[arg(long, help = "Select everything but the given positions")]
+    complement: bool,
+
+    #[arg(
+        long,
+        help = "Print positions in input order, once each (default)",
+        overrides_with = "reorder"
+    )]
+    posix: bool,
+
+    #[arg(
+        long,
+        help = "Print positions in the order given, repeats included",
+        overrides_with = "posix"
+    )]
+    reorder: bool,
+
+    #[arg(
+        long,
+        help = "Read fields as CSV, so quoted fields may hold delimiters (default for a one byte delimiter)",
+        overrides_with = "raw"
+    )]
+    csv: bool,
+
+    #[arg(
+        long,
+        help = "Split lines on every delimiter, taking quotes as they are",
+        overrides_with = "csv"
+    )]
+    raw: bool,
+
+    #[arg(
+        short = 's',
+        long,
+        help = "Leave out lines without a delimiter, which are printed whole otherwise"
+    )]
+    only_delimited: bool,
+
+    #[arg(
+        long,
+        help = "Keep CSV rows whose number of fields differs from the first row's, which are skipped otherwise"
+    )]
+    flexible: bool,
+
+    #[arg(long, help = "The first line holds the field names")]
+    header: bool,
+
+    #[arg(
+        long = "where",
+        help = "Only print rows whose field passes, like `col3 > 100`, `name ~ /regex/` or `status == \"ok\"`",
+        value_name = "EXPR",
+        conflicts_with_all = ["bytes", "chars"],
+        value_parser = Condition::parse
+    )]
+    conditions: Vec<Condition>,
 
     #[arg(help = "Input file(s)", default_value = "-", value_name = "FILE")]
     files: Vec<String>,
 }
 
 impl Opts {
-    pub fn to_config(self) -> Config {
-        let extract = match (self.fields, self.bytes, self.chars) {
-            (Some(pos), None, None) => Extract::Fields(pos),
-            (None, Some(pos), None) => Extract::Bytes(pos),
-            (None, None, Some(pos)) => Extract::Chars(pos),
-            (None, None, None) => unreachable!("At least one of the args is required"),
-            (_, _, _) => unreachable!("the arguments conflict"),
+    pub fn to_config(self) -> CutrResult<Config> {
+        let order = |pos| if self.reorder { pos } else { merge(pos) };
+        let extract = match (self.fields, self.field_names, self.bytes, self.chars) {
+            (Some(pos), None, None, None) => Extract::Fields(order(pos)),
+            (None, Some(names), None, None) => Extract::FieldNames(names),
+            (None, None, Some(pos), None) => Extract::Bytes(order(pos)),
+            (None, None, None, Some(pos)) if self.graphemes => Extract::Graphemes(order(pos)),
+            (None, None, None, Some(pos)) if self.display_width => Extract::Columns(order(pos)),
+            (None, None, None, Some(pos)) => Extract::Chars(order(pos)),
+            (None, None, None, None) => {
+                return Err("Must have --fields, --field-names, --bytes, or --chars".into())
+            }
+            (_, _, _, _) => unreachable!("the arguments conflict"),
         };
 
-        Config {
-            delimiter: self.delimiter,
+        let delimiter = match self.regex_delimiter {
+            Some(regex) => Delimiter::Regex(regex),
+            None => Delimiter::Text(self.delimiter),
+        };
+        let csv = matches!(&delimiter, Delimiter::Text(delim) if delim.len() == 1);
+        if self.csv && !csv {
+            return Err("--csv needs a one byte --delimiter".into());
+        }
+        let output_delimiter = self.output_delimiter.unwrap_or_else(|| match &delimiter {
+            Delimiter::Text(delim) => delim.clone(),
+            Delimiter::Regex(_) => "\t".to_string(),
+        });
+
+        Ok(Config {
+            delimiter,
+            output_delimiter,
+            output_format: self.output_format,
+            csv: csv && !self.raw,
             extract,
+            complement: self.complement,
+            whole_chars: self.whole_chars,
+            raw_bytes: self.raw_bytes,
+            only_delimited: self.only_delimited,
+            flexible: self.flexible,
+            header: self.header,
+            conditions: self.conditions,
+            reorder: self.reorder,
             files: self.files,
-        }
+        })
     }
 }
 
 #[derive(Debug)]
 pub enum Extract {
     Fields(PositionList),
+    /// Fields named in the header, maybe with wildcards
+    FieldNames(Vec<String>),
     Bytes(PositionList),
     Chars(PositionList),
+    /// Extended grapheme clusters
+    Graphemes(PositionList),
+    /// Terminal columns
+    Columns(PositionList),
+}
+
+/// What separates the fields of a line
+#[derive(Debug, Clone)]
+pub enum Delimiter {
+    Text(String),
+    Regex(Regex),
+}
+
+impl Delimiter {
+    fn split<'a>(&self, line: &'a str) -> Vec<&'a str> {
+        match self {
+            Self::Text(delim) => line.split(delim.as_str()).collect(),
+            Self::Regex(regex) => {
+                // Like awk, a delimiter at the start of the line doesn't make
+                // an empty first field, so right-aligned columns line up
+                let line = match regex.find(line) {
+                    Some(m) if m.start() == 0 => &line[m.end()..],
+                    _ => line,
+                };
+                regex.split(line).collect()
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct Config {
-    delimiter: u8,
+    delimiter: Delimiter,
+    output_delimiter: String,
+    output_format: Option<OutputFormat>,
+    /// Fields are read as CSV, and written as CSV when they can be
+    csv: bool,
     extract: Extract,
+    complement: bool,
+    whole_chars: bool,
+    raw_bytes: bool,
+    only_delimited: bool,
+    flexible: bool,
+    header: bool,
+    conditions: Vec<Condition>,
+    reorder: bool,
     files: Vec<String>,
 }
 
-type CutrResult<T> = Result<T, Box<dyn Error>>;
+pub(crate) type CutrResult<T> = Result<T, Box<dyn Error>>;
 
 pub fn run(cfg: Config) -> CutrResult<()> {
+    // One writer for all files, so JSON is a single array and a Markdown
+    // table has a single header
+    let mut writer = FieldWriter::new(cfg.output_format, &cfg.output_delimiter, cfg.csv);
+    let cut_rows = matches!(cfg.extract, Extract::Fields(_) | Extract::FieldNames(_));
     for filename in &cfg.files {
         match open(filename) {
             Err(err) => eprintln!("{}: {}", filename, err),
             Ok(reader) => match &cfg.extract {
-                Extract::Chars(pos) => reader
-                    .lines()
-                    .filter_map(|line| line.ok())
-                    .for_each(|line| println!("{}", extract_chars(&line, pos))),
-
-                Extract::Bytes(pos) => reader
-                    .lines()
-                    .filter_map(|line| line.ok())
-                    .for_each(|line| println!("{}", extract_bytes(&line, pos))),
-                    
-                Extract::Fields(pos) => {
-                    let mut reader = ReaderBuilder::new()
-                        .delimiter(cfg.delimiter)
-                        .has_headers(false)
-                        .from_reader(reader);
-
-                    let mut writer = WriterBuilder::new()
-                        .delimiter(cfg.delimiter)
-                        .from_writer(io::stdout());
-
-                    for record in reader.records() {
-                        let record = record?;
-                        writer.write_record(extract_fields(&record, pos))?;
-                    }
+                Extract::Chars(pos) => cut_chars(&cfg, pos, reader, extract_chars)?,
+                Extract::Graphemes(pos) => cut_chars(&cfg, pos, reader, extract_graphemes)?,
+                Extract::Columns(pos) => cut_chars(&cfg, pos, reader, extract_columns)?,
+
+                Extract::Bytes(pos) => cut_bytes(&cfg, pos, reader)?,
+
+                Extract::Fields(_) | Extract::FieldNames(_) => {
+                    cut_fields(&cfg, filename, reader, &mut writer)?
                 }
             },
         }
     }
+    if cut_rows {
+        writer.finish()?;
+    }
     Ok(())
 }
 
-fn extract_chars(line: &str, char_pos: &[Range<usize>]) -> String {
-    let chars: Vec<_> = line.chars().collect();
-    let mut output: Vec<char> = vec![];
+/// The lines of `reader`, without their line endings
+fn byte_lines(reader: Box<dyn BufRead>) -> impl Iterator<Item = io::Result<Vec<u8>>> {
+    reader.split(b'\n').map(|line| {
+        line.map(|mut line| {
+            if line.ends_with(b"\r") {
+                line.pop();
+            }
+            line
+        })
+    })
+}
+
+/// The lines of `reader`, with bytes that aren't valid UTF-8 replaced so
+/// that no line is left out
+fn lossy_lines(reader: Box<dyn BufRead>) -> impl Iterator<Item = io::Result<String>> {
+    byte_lines(reader).map(|line| line.map(|line| String::from_utf8_lossy(&line).into_owned()))
+}
 
-    for range in char_pos.into_iter().cloned() {
-        for i in range {
-            if let Some(val) = chars.get(i) {
-                output.push(*val);
+/// Prints what `extract` selects from each line: chars, graphemes or
+/// columns
+fn cut_chars(
+    cfg: &Config,
+    pos: &[Range<usize>],
+    reader: Box<dyn BufRead>,
+    extract: fn(&str, &[Range<usize>], bool) -> String,
+) -> CutrResult<()> {
+    let mut stdout = io::stdout().lock();
+    for line in lossy_lines(reader) {
+        writeln!(stdout, "{}", extract(&line?, pos, cfg.complement))?;
+    }
+    Ok(())
+}
+
+/// Prints the selected bytes of each line. Lines are read as bytes, so
+/// ones that aren't valid UTF-8 are cut too.
+fn cut_bytes(cfg: &Config, pos: &[Range<usize>], reader: Box<dyn BufRead>) -> CutrResult<()> {
+    let mut stdout = io::stdout().lock();
+    for line in byte_lines(reader) {
+        let line = line?;
+        let output = extract_bytes(&line, pos, cfg.complement, cfg.whole_chars);
+        if cfg.raw_bytes {
+            stdout.write_all(&output)?;
+            stdout.write_all(b"\n")?;
+        } else {
+            writeln!(stdout, "{}", String::from_utf8_lossy(&output))?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints the selected fields of the rows that pass the `--where`
+/// conditions, all of them. CSV records are cut where they are read, and
+/// other lines are split on the delimiter.
+fn cut_fields(
+    cfg: &Config,
+    filename: &str,
+    reader: Box<dyn BufRead>,
+    writer: &mut FieldWriter,
+) -> CutrResult<()> {
+    match &cfg.delimiter {
+        Delimiter::Text(delim) if cfg.csv => {
+            let mut reader = ReaderBuilder::new()
+                .delimiter(delim.as_bytes()[0])
+                .has_headers(cfg.header)
+                .flexible(true)
+                .from_reader(reader);
+            // Records are read as bytes, so fields that aren't valid UTF-8
+            // are cut too, as they are with --raw
+            let headers = if cfg.header {
+                let headers = lossy_record(reader.byte_headers()?.clone());
+                Some(headers.iter().map(String::from).collect::<Vec<_>>())
+                    .filter(|headers| !headers.is_empty())
+            } else {
+                None
+            };
+            let mut cutter = RowCutter::new(cfg, filename, headers.as_deref(), writer)?;
+            let mut record = ByteRecord::new();
+            while reader.read_byte_record(&mut record)? {
+                let row = lossy_record(record);
+                cutter.cut(&row)?;
+                record = row.into_byte_record();
+            }
+        }
+        delimiter => {
+            let mut lines = lossy_lines(reader);
+            let headers = if cfg.header {
+                let line = lines.next().transpose()?;
+                line.map(|line| {
+                    delimiter
+                        .split(&line)
+                        .into_iter()
+                        .map(String::from)
+                        .collect::<Vec<_>>()
+                })
+            } else {
+                None
+            };
+            let mut cutter = RowCutter::new(cfg, filename, headers.as_deref(), writer)?;
+            for line in lines {
+                cutter.cut(delimiter.split(&line?).as_slice())?;
             }
         }
     }
+    Ok(())
+}
 
-    output.iter().collect()
+/// `record` as text, with bytes that aren't valid UTF-8 replaced
+fn lossy_record(record: ByteRecord) -> StringRecord {
+    StringRecord::from_byte_record(record)
+        .unwrap_or_else(|err| StringRecord::from_byte_record_lossy(err.into_byte_record()))
 }
 
-fn extract_bytes(line: &str, byte_pos: &[Range<usize>]) -> String {
-    let bytes = line.as_bytes();
-    let output: Vec<_> = byte_pos
-        .iter()
-        .cloned()
-        .flat_map(|range| range.filter_map(|i| bytes.get(i).copied()))
-        .collect();
+/// Cuts the rows of one file, with the fields and conditions looked up in
+/// its header. Like POSIX cut, lines without a delimiter are printed whole
+/// unless `--only-delimited` is given. CSV rows with a different number of
+/// fields than the first are reported and skipped unless `--flexible` is
+/// given.
+struct RowCutter<'a> {
+    cfg: &'a Config,
+    filename: &'a str,
+    pos: PositionList,
+    conditions: Vec<(usize, &'a Condition)>,
+    width: Option<usize>,
+    /// The number of the last row read, counting the header
+    row_num: usize,
+    writer: &'a mut FieldWriter,
+}
+
+impl<'a> RowCutter<'a> {
+    /// Prints `headers`, after the fields and conditions are looked up in it
+    fn new(
+        cfg: &'a Config,
+        filename: &'a str,
+        headers: Option<&[String]>,
+        writer: &'a mut FieldWriter,
+    ) -> CutrResult<Self> {
+        let pos = match &cfg.extract {
+            Extract::FieldNames(names) => {
+                let pos = name_pos(names, headers.unwrap_or_default())?;
+                if cfg.reorder {
+                    pos
+                } else {
+                    merge(pos)
+                }
+            }
+            Extract::Fields(pos) => pos.clone(),
+            _ => unreachable!("only fields are cut as rows"),
+        };
+        let conditions = cfg
+            .conditions
+            .iter()
+            .map(|condition| Ok((condition.index(headers)?, condition)))
+            .collect::<CutrResult<Vec<_>>>()?;
+
+        if let Some(headers) = headers {
+            writer.header(headers, &select(&pos, headers.len(), cfg.complement))?;
+        }
+        Ok(RowCutter {
+            cfg,
+            filename,
+            pos,
+            conditions,
+            width: headers.map(<[String]>::len).filter(|&len| len > 1),
+            row_num: usize::from(headers.is_some()),
+            writer,
+        })
+    }
+
+    fn cut(&mut self, row: &(impl Row + ?Sized)) -> CutrResult<()> {
+        self.row_num += 1;
+        if !self
+            .conditions
+            .iter()
+            .all(|(i, condition)| condition.is_match(row.get(*i)))
+        {
+            return Ok(());
+        }
+        if row.len() < 2 {
+            if !self.cfg.only_delimited {
+                self.writer.write(row, &[0])?;
+            }
+            return Ok(());
+        }
+
+        match self.width {
+            None => self.width = Some(row.len()),
+            Some(width) if self.cfg.csv && !self.cfg.flexible && row.len() != width => {
+                eprintln!(
+                    "{}: skipped row {} with {} fields instead of {}, see --flexible",
+                    self.filename,
+                    self.row_num,
+                    row.len(),
+                    width
+                );
+                return Ok(());
+            }
+            _ => {}
+        }
+        let selected = select(&self.pos, row.len(), self.cfg.complement);
+        self.writer.write(row, &selected)
+    }
+}
+
+/// The positions of the fields called `names` in `headers`, in the order
+/// given. A name with wildcards selects every header it matches, in the
+/// order of the headers.
+fn name_pos(names: &[String], headers: &[String]) -> CutrResult<PositionList> {
+    let mut pos = vec![];
+    for name in names {
+        let pattern = format!("^{}$", regex::escape(name))
+            .replace(r"\*", ".*")
+            .replace(r"\?", ".");
+        let name_re = Regex::new(&pattern)?;
+        let before = pos.len();
+        pos.extend(
+            headers
+                .iter()
+                .enumerate()
+                .filter(|(_, header)| name_re.is_match(header))
+                .map(|(i, _)| i..i + 1),
+        );
+
+        if pos.len() == before {
+            return Err(format!(
+                "unknown field name: {} (the headers are: {})",
+                name,
+                headers.join(", ")
+            )
+            .into());
+        }
+    }
+    Ok(pos)
+}
+
+/// Sorts `pos` and joins the ranges that overlap or touch
+fn merge(mut pos: PositionList) -> PositionList {
+    pos.sort_by_key(|range| range.start);
+    let mut merged: PositionList = Vec::with_capacity(pos.len());
+    for range in pos {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
 
-    String::from_utf8_lossy(&output).to_string()
+/// The indexes selected by `pos` in a line of `len` chars, bytes, fields,
+/// graphemes or columns.
+/// With `complement` these are the ones not in `pos`, in order.
+fn select(pos: &[Range<usize>], len: usize, complement: bool) -> Vec<usize> {
+    if complement {
+        (0..len)
+            .filter(|i| !pos.iter().any(|range| range.contains(i)))
+            .collect()
+    } else {
+        pos.iter()
+            .flat_map(|range| range.start.min(len)..range.end.min(len))
+            .collect()
+    }
 }
 
-fn extract_fields<'rec>(record: &'rec StringRecord, field_pos: &[Range<usize>]) -> Vec<&'rec str> {
-    field_pos
+fn extract_chars(line: &str, char_pos: &[Range<usize>], complement: bool) -> String {
+    let chars: Vec<_> = line.chars().collect();
+    select(char_pos, chars.len(), complement)
+        .into_iter()
+        .map(|i| chars[i])
+        .collect()
+}
+
+fn extract_graphemes(line: &str, grapheme_pos: &[Range<usize>], complement: bool) -> String {
+    let graphemes: Vec<_> = line.graphemes(true).collect();
+    select(grapheme_pos, graphemes.len(), complement)
+        .into_iter()
+        .map(|i| graphemes[i])
+        .collect()
+}
+
+/// Selects the grapheme clusters of `line` that sit in the selected terminal
+/// columns. A wide one is only printed when all its columns are selected in
+/// a row, so the output is never wider than the selection.
+fn extract_columns(line: &str, column_pos: &[Range<usize>], complement: bool) -> String {
+    let mut graphemes = vec![];
+    let mut width = 0;
+    for grapheme in line.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        graphemes.push((width, grapheme_width, grapheme));
+        width += grapheme_width;
+    }
+
+    let columns = select(column_pos, width, complement);
+    let mut output = String::new();
+    let mut i = 0;
+    while i < columns.len() {
+        let column = columns[i];
+        let mut step = 1;
+        // Zero width clusters share their column with the next one
+        let first = graphemes.partition_point(|&(start, _, _)| start < column);
+        for &(_, grapheme_width, grapheme) in graphemes[first..]
+            .iter()
+            .take_while(|&&(start, _, _)| start == column)
+        {
+            if columns[i..]
+                .iter()
+                .copied()
+                .take(grapheme_width)
+                .eq(column..column + grapheme_width)
+            {
+                output.push_str(grapheme);
+                step = step.max(grapheme_width);
+            }
+        }
+        i += step;
+    }
+    output
+}
+
+fn extract_bytes(
+    bytes: &[u8],
+    byte_pos: &[Range<usize>],
+    complement: bool,
+    whole_chars: bool,
+) -> Vec<u8> {
+    let char_pos;
+    let byte_pos = if whole_chars {
+        char_pos = char_bounds(bytes, byte_pos, complement);
+        &char_pos
+    } else {
+        byte_pos
+    };
+
+    select(byte_pos, bytes.len(), complement)
+        .into_iter()
+        .map(|i| bytes[i])
+        .collect()
+}
+
+/// Moves the ends of the ranges in `byte_pos` to the character boundaries
+/// of `bytes`, as POSIX `cut -n` does. Both move back, so a range that
+/// starts inside a character takes in all of it, and one that ends inside
+/// a character stops before it. With `complement` the ends move out
+/// instead, so what is left also holds whole characters.
+fn char_bounds(bytes: &[u8], byte_pos: &[Range<usize>], complement: bool) -> PositionList {
+    let is_bound = |i: usize| i >= bytes.len() || bytes[i] & 0xc0 != 0x80;
+    let floor = |mut i: usize| {
+        while i > 0 && !is_bound(i) {
+            i -= 1;
+        }
+        i
+    };
+    let ceil = |mut i: usize| {
+        while !is_bound(i) {
+            i += 1;
+        }
+        i
+    };
+
+    byte_pos
         .iter()
-        .cloned()
-        .flat_map(|range| range.filter_map(|i| record.get(i)))
+        .map(|range| (range.start.min(bytes.len()), range.end.min(bytes.len())))
+        .map(|(start, end)| {
+            if complement {
+                floor(start)..ceil(end)
+            } else {
+                floor(start)..floor(end)
+            }
+        })
+        .filter(|range| !range.is_empty())
         .collect()
 }
 
@@ -138,30 +661,37 @@ fn open(filename: &str) -> CutrResult<Box<dyn BufRead>> {
     }
 }
 
-fn parse_delim(delim: &str) -> Result<u8, Box<dyn Error + Send + Sync + 'static>> {
-    let bytes = delim.as_bytes();
-    if bytes.len() != 1 {
-        return Err(From::from(format!(
-            "--delim \"{}\" must be a single byte",
-            delim
-        )));
+fn parse_delim(delim: &str) -> Result<String, Box<dyn Error + Send + Sync + 'static>> {
+    if delim.is_empty() {
+        return Err(From::from("--delim must not be empty"));
     }
 
-    Ok(bytes[0])
+    Ok(delim.to_string())
 }
 
 fn parse_pos(range: &str) -> Result<PositionList, Box<dyn Error + 'static + Send + Sync>> {
-    let range_re = Regex::new(r"^(\d+)-(\d+)$").unwrap();
+    let range_re = Regex::new(r"^(\d*)-(\d*)$").unwrap();
     range
-        .split(",")
-        .into_iter()
+        .split(',')
         .map(|value| {
             parse_index(value).map(|n| n..n + 1).or_else(|e| {
                 range_re.captures(value).ok_or(e).and_then(|captures| {
-                    let n1 = parse_index(&captures[1])?;
-                    let n2 = parse_index(&captures[2])?;
+                    let (from, to) = (&captures[1], &captures[2]);
+                    if from.is_empty() && to.is_empty() {
+                        return Err(format!("illegal list value: \"{value}\""));
+                    }
+                    // `-M` starts at the first position and `N-` runs to the end
+                    let n1 = if from.is_empty() {
+                        0
+                    } else {
+                        parse_index(from)?
+                    };
+                    if to.is_empty() {
+                        return Ok(n1..usize::MAX);
+                    }
+                    let n2 = parse_index(to)?;
 
-                    if n1 >= n2 {
+                    if !from.is_empty() && n1 >= n2 {
                         return Err(format!(
                             "First number in range ({}) must be lower than second number ({})",
                             n1 + 1,
@@ -178,20 +708,21 @@ fn parse_pos(range: &str) -> Result<PositionList, Box<dyn Error + 'static + Send
 }
 
 fn parse_index(idx: &str) -> Result<usize, String> {
-    let value_error = || format!("illegal list value \"{idx}\"");
-
-    idx.starts_with("+")
-        .then(|| Err(value_error()))
-        .unwrap_or_else(|| {
-            idx.parse::<NonZeroUsize>()
-                .map(|n| usize::from(n) - 1)
-                .map_err(|_| value_error())
-        })
+    let value_error = || format!("illegal list value: \"{idx}\"");
+
+    if idx.starts_with('+') {
+        Err(value_error())
+    } else {
+        idx.parse::<NonZeroUsize>()
+            .map(|n| usize::from(n) - 1)
+            .map_err(|_| value_error())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_pos;
+    use super::{char_bounds, extract_columns, merge, parse_pos, select, Condition};
+    use std::ops::Range;
 
     #[test]
     fn test_parse_pos() {
@@ -202,37 +733,37 @@ mod tests {
     fn test_zero_is_error() {
         let res = parse_pos("0");
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value \"0\"");
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"0\"");
 
         let res = parse_pos("0-1");
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value \"0\"");
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"0\"");
     }
 
     #[test]
     fn test_leading_plus_is_error() {
         let res = parse_pos("+1");
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value \"+1\"");
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"+1\"");
 
         let res = parse_pos("+1-2");
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value \"+1-2\"");
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"+1-2\"");
     }
 
     #[test]
     fn test_non_number_is_error() {
         let res = parse_pos("a");
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value \"a\"");
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a\"");
 
         let res = parse_pos("1-a");
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value \"1-a\"");
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"1-a\"");
 
         let res = parse_pos("a-1");
         assert!(res.is_err());
-        assert_eq!(res.unwrap_err().to_string(), "illegal list value \"a-1\"");
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"a-1\"");
     }
 
     #[test]
@@ -246,9 +777,6 @@ mod tests {
         let res = parse_pos("1,");
         assert!(res.is_err());
 
-        let res = parse_pos("1-");
-        assert!(res.is_err());
-
         let res = parse_pos("1-1-1");
         assert!(res.is_err());
     }
@@ -296,4 +824,92 @@ mod tests {
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), vec![14..15, 18..20]);
     }
+
+    #[test]
+    fn test_open_ended_ranges() {
+        let res = parse_pos("1-");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..usize::MAX]);
+
+        let res = parse_pos("-3");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..3]);
+
+        let res = parse_pos("2,4-");
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![1..2, 3..usize::MAX]);
+
+        let res = parse_pos("-0");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), "illegal list value: \"0\"");
+    }
+
+    #[test]
+    fn test_select() {
+        let pos = |list| parse_pos(list).unwrap();
+        assert_eq!(select(&pos("2-"), 4, false), vec![1, 2, 3]);
+        assert_eq!(select(&pos("1-2"), 1, false), vec![0]);
+        assert_eq!(select(&pos("3,1"), 4, false), vec![2, 0]);
+        assert_eq!(select(&pos("3,1"), 4, true), vec![1, 3]);
+        assert_eq!(select(&pos("2-"), 4, true), vec![0]);
+    }
+
+    #[test]
+    fn test_merge() {
+        let pos = |list| parse_pos(list).unwrap();
+        assert_eq!(merge(pos("1,7,3-5")), pos("1,3-5,7"));
+        assert_eq!(merge(pos("3,1-2")), pos("1-3"));
+        assert_eq!(merge(pos("1,1")), pos("1"));
+        assert_eq!(merge(pos("4-,2-5")), pos("2-"));
+        assert_eq!(merge(pos("2-3,1-4")), pos("1-4"));
+    }
+
+    #[test]
+    fn test_extract_columns() {
+        let pos = |list| parse_pos(list).unwrap();
+        assert_eq!(extract_columns("日本ab", &pos("1-4"), false), "日本");
+        assert_eq!(extract_columns("日本ab", &pos("2-5"), false), "本a");
+        assert_eq!(extract_columns("日本ab", &pos("2"), true), "本ab");
+        assert_eq!(extract_columns("e\u{301}x", &pos("1"), false), "e\u{301}");
+        assert_eq!(extract_columns("ab日", &pos("3-,1"), false), "日a");
+    }
+
+    #[test]
+    fn test_char_bounds() {
+        let pos = |list| parse_pos(list).unwrap();
+        let bytes = "aÉb".as_bytes();
+        assert_eq!(
+            char_bounds(bytes, &pos("2"), false),
+            Vec::<Range<usize>>::new()
+        );
+        assert_eq!(char_bounds(bytes, &pos("3"), false), pos("2-3"));
+        assert_eq!(char_bounds(bytes, &pos("1-2"), false), pos("1"));
+        assert_eq!(char_bounds(bytes, &pos("2-"), false), pos("2-4"));
+        assert_eq!(char_bounds(bytes, &pos("2"), true), pos("2-3"));
+        assert_eq!(char_bounds(&[0x80, b'a'], &pos("1"), false), pos("1"));
+    }
+
+    #[test]
+    fn test_condition() {
+        let passes = |expr, field| Condition::parse(expr).unwrap().is_match(field);
+        assert!(passes("col3 > 100", Some("120")));
+        assert!(passes("col3 > 100", Some(" 1e3")));
+        assert!(!passes("col3 > 100", Some("80")));
+        assert!(!passes("col3 > 100", Some("n/a")));
+        assert!(passes("col3 != 100", Some("n/a")));
+        assert!(!passes("col3 > 100", Some("inf")));
+        assert!(passes("col1 == nan", Some("nan")));
+        assert!(passes("col1 == inf", Some("inf")));
+        assert!(!passes("col1 == infinity", Some("inf")));
+        assert!(passes("col1 < .5", Some("-1.5")));
+        assert!(passes("col1 <= \"b\"", Some("a")));
+        assert!(passes("status == ok", Some("ok")));
+        assert!(passes("status == \"ok go\"", Some("ok go")));
+        assert!(passes("name ~ /^a.*a$/", Some("alpha")));
+        assert!(passes("name !~ x", Some("alpha")));
+        assert!(!passes("col2 == 1", None));
+        assert!(passes("col2 != 1", None));
+        assert!(Condition::parse("col0 == 1").is_err());
+        assert!(Condition::parse("== 1").is_err());
+    }
 }
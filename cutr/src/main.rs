@@ -3,6 +3,6 @@ use cutr::{run, Opts};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opts = Opts::parse();
-    run(opts.to_config())?;
+    run(opts.to_config()?)?;
     Ok(())
 }
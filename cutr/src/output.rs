@@ -0,0 +1,182 @@
+use crate::CutrResult;
+use clap::ValueEnum;
+use csv::{StringRecord, Writer, WriterBuilder};
+use std::io::{self, Write};
+
+/// How the selected fields are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Csv,
+    Tsv,
+    /// An array of objects keyed by the header
+    Json,
+    /// An object keyed by the header on each line
+    Jsonl,
+    /// A table with the header on top
+    Markdown,
+}
+
+/// The fields of a row, borrowed from a CSV record or a split line
+pub(crate) trait Row {
+    fn len(&self) -> usize;
+    fn get(&self, i: usize) -> Option<&str>;
+}
+
+impl Row for StringRecord {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, i: usize) -> Option<&str> {
+        self.get(i)
+    }
+}
+
+impl<T: AsRef<str>> Row for [T] {
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn get(&self, i: usize) -> Option<&str> {
+        self.get(i).map(AsRef::as_ref)
+    }
+}
+
+/// Prints the selected fields of each row. Rows are given whole along with
+/// the indexes of their selected fields, which JSON looks up in the header.
+pub(crate) enum FieldWriter {
+    /// Quoted where needed
+    Csv(Box<Writer<io::Stdout>>),
+    /// Joined as they are
+    Joined(String),
+    Json {
+        lines: bool,
+        names: Vec<String>,
+        rows: usize,
+    },
+    Markdown {
+        has_header: bool,
+    },
+}
+
+impl FieldWriter {
+    /// Without a `format`, fields read as CSV are written as CSV if
+    /// `delimiter` is one byte, and are joined with it otherwise
+    pub(crate) fn new(format: Option<OutputFormat>, delimiter: &str, csv: bool) -> Self {
+        match format {
+            Some(OutputFormat::Csv) => Self::csv(b','),
+            Some(OutputFormat::Tsv) => Self::csv(b'\t'),
+            Some(OutputFormat::Json) => Self::json(false),
+            Some(OutputFormat::Jsonl) => Self::json(true),
+            Some(OutputFormat::Markdown) => Self::Markdown { has_header: false },
+            None if csv && delimiter.len() == 1 => Self::csv(delimiter.as_bytes()[0]),
+            None => Self::Joined(delimiter.to_string()),
+        }
+    }
+
+    fn csv(delimiter: u8) -> Self {
+        Self::Csv(Box::new(
+            WriterBuilder::new()
+                .delimiter(delimiter)
+                .flexible(true)
+                .from_writer(io::stdout()),
+        ))
+    }
+
+    fn json(lines: bool) -> Self {
+        Self::Json {
+            lines,
+            names: vec![],
+            rows: 0,
+        }
+    }
+
+    /// Prints the header row, or keeps its names to key JSON objects with.
+    /// A Markdown table only gets the header of the first file.
+    pub(crate) fn header(&mut self, headers: &[String], selected: &[usize]) -> CutrResult<()> {
+        match self {
+            Self::Json { names, .. } => *names = headers.to_vec(),
+            Self::Markdown { has_header: true } => {}
+            Self::Markdown { has_header } => {
+                print_markdown_header(selected.iter().map(|&i| headers[i].clone()));
+                *has_header = true;
+            }
+            _ => self.write(headers, selected)?,
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write(
+        &mut self,
+        row: &(impl Row + ?Sized),
+        selected: &[usize],
+    ) -> CutrResult<()> {
+        let fields = selected.iter().map(|&i| row.get(i).unwrap_or_default());
+        match self {
+            Self::Csv(writer) => writer.write_record(fields)?,
+            Self::Joined(delimiter) => println!("{}", fields.collect::<Vec<_>>().join(delimiter)),
+            Self::Json { lines, names, rows } => {
+                let members = selected
+                    .iter()
+                    .map(|&i| {
+                        let name = names.get(i).cloned().unwrap_or((i + 1).to_string());
+                        Ok(format!(
+                            "{}:{}",
+                            serde_json::to_string(&name)?,
+                            serde_json::to_string(row.get(i).unwrap_or_default())?
+                        ))
+                    })
+                    .collect::<serde_json::Result<Vec<_>>>()?;
+                let object = format!("{{{}}}", members.join(","));
+
+                if *lines {
+                    println!("{}", object);
+                } else if *rows == 0 {
+                    print!("[\n  {}", object);
+                } else {
+                    print!(",\n  {}", object);
+                }
+                *rows += 1;
+            }
+            Self::Markdown { has_header } => {
+                if !*has_header {
+                    // Without a header the columns are named by number
+                    print_markdown_header(selected.iter().map(|i| (i + 1).to_string()));
+                    *has_header = true;
+                }
+                print_markdown_row(fields);
+            }
+        }
+        Ok(())
+    }
+
+    /// Ends the output, after the last file
+    pub(crate) fn finish(&mut self) -> CutrResult<()> {
+        match self {
+            Self::Csv(writer) => writer.flush()?,
+            Self::Json {
+                lines: false, rows, ..
+            } => match rows {
+                0 => println!("[]"),
+                _ => println!("\n]"),
+            },
+            _ => {}
+        }
+        io::stdout().flush()?;
+        Ok(())
+    }
+}
+
+fn print_markdown_header(names: impl ExactSizeIterator<Item = String>) {
+    let len = names.len();
+    print_markdown_row(names.collect::<Vec<_>>().iter().map(String::as_str));
+    println!("|{}", "---|".repeat(len));
+}
+
+/// Prints a table row, with the pipes and line breaks in cells escaped
+fn print_markdown_row<'a>(cells: impl Iterator<Item = &'a str>) {
+    let cells: Vec<_> = cells
+        .map(|cell| cell.replace('|', r"\|").replace('\n', "<br>"))
+        .collect();
+    println!("| {} |", cells.join(" | "));
+}
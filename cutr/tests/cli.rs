@@ -35,7 +35,7 @@ fn skips_bad_file() -> TestResult {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error 2[)]", bad);
     Command::cargo_bin(PRG)?
-        .args(&["-f", "1", CSV, &bad, TSV])
+        .args(["-f", "1", CSV, &bad, TSV])
         .assert()
         .success()
         .stderr(predicate::str::is_match(expected)?);
@@ -55,7 +55,10 @@ fn dies(args: &[&str], expected: &str) -> TestResult {
 // --------------------------------------------------
 #[test]
 fn dies_not_enough_args() -> TestResult {
-    dies(&[CSV], "Must have --fields, --bytes, or --chars")
+    dies(
+        &[CSV],
+        "Must have --fields, --field-names, --bytes, or --chars",
+    )
 }
 
 // --------------------------------------------------
@@ -91,26 +94,33 @@ fn dies_bad_digit_chars() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn dies_empty_delimiter() -> TestResult {
-    dies(
-        &[CSV, "-f", "1", "-d", ""],
-        "--delim \"\" must be a single byte",
-    )
+    dies(&[CSV, "-f", "1", "-d", ""], "--delim must not be empty")
 }
 
 // --------------------------------------------------
 #[test]
-fn dies_bad_delimiter() -> TestResult {
+fn dies_bad_regex_delimiter() -> TestResult {
     dies(
-        &[CSV, "-f", "1", "-d", ",,"],
-        "--delim \",,\" must be a single byte",
+        &[CSV, "-f", "1", "--regex-delimiter", "("],
+        "regex parse error",
     )
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_delimiter_regex_delimiter() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([CSV, "-f", "1", "-d", ",", "--regex-delimiter", ","])
+        .assert()
+        .failure();
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn dies_chars_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -120,7 +130,7 @@ fn dies_chars_bytes_fields() -> TestResult {
 #[test]
 fn dies_bytes_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-f", "1", "-b", "1"])
+        .args([CSV, "-f", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -130,7 +140,7 @@ fn dies_bytes_fields() -> TestResult {
 #[test]
 fn dies_chars_fields() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-f", "1"])
+        .args([CSV, "-c", "1", "-f", "1"])
         .assert()
         .failure();
     Ok(())
@@ -140,7 +150,7 @@ fn dies_chars_fields() -> TestResult {
 #[test]
 fn dies_chars_bytes() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&[CSV, "-c", "1", "-b", "1"])
+        .args([CSV, "-c", "1", "-b", "1"])
         .assert()
         .failure();
     Ok(())
@@ -335,5 +345,776 @@ fn tsv_c1_8() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn repeated_value() -> TestResult {
-    run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1,1.out")
+    run(
+        &[BOOKS, "-c", "1,1", "--reorder"],
+        "tests/expected/books.c1,1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_open() -> TestResult {
+    run(&[TSV, "-f", "2-"], "tests/expected/movies1.tsv.f2-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f_open_2() -> TestResult {
+    run(&[TSV, "-f", "-2"], "tests/expected/movies1.tsv.f-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c8_open() -> TestResult {
+    run(&[TSV, "-c", "8-"], "tests/expected/movies1.tsv.c8-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b2_open() -> TestResult {
+    run(&[TSV, "-b", "2-"], "tests/expected/movies1.tsv.b2-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_complement() -> TestResult {
+    run(
+        &[TSV, "-f", "2", "--complement"],
+        "tests/expected/movies1.tsv.f2.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c_open_8_complement() -> TestResult {
+    run(
+        &[TSV, "-c", "-8", "--complement"],
+        "tests/expected/movies1.tsv.c-8.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b1_3_complement() -> TestResult {
+    run(
+        &[TSV, "-b", "1,3", "--complement"],
+        "tests/expected/movies1.tsv.b1,3.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_f1_3_open_complement() -> TestResult {
+    run(
+        &[CSV, "-f", "1,3-", "--complement", "-d", ","],
+        "tests/expected/movies1.csv.f1,3-.complement.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_field_names_glob() -> TestResult {
+    run(
+        &[CSV, "--header", "-F", "title,dir*", "-d", ","],
+        "tests/expected/movies1.csv.Ftitle,dir.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_field_names_reordered() -> TestResult {
+    run(
+        &[CSV, "--header", "-F", "year,title", "-d", ",", "--reorder"],
+        "tests/expected/movies1.csv.Fyear,title.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_field_names_complement() -> TestResult {
+    run(
+        &[BOOKS, "--header", "-F", "?ear", "--complement"],
+        "tests/expected/books.Fyear.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_field_name() -> TestResult {
+    dies(
+        &[CSV, "--header", "-F", "title,foo", "-d", ","],
+        "unknown field name: foo (the headers are: title, year, director)",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_field_names_without_header() -> TestResult {
+    dies(&[CSV, "-F", "title", "-d", ","], "--header")
+}
+
+// --------------------------------------------------
+#[test]
+fn repeated_value_posix() -> TestResult {
+    run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f3_1_posix() -> TestResult {
+    run(&[TSV, "-f", "3,1"], "tests/expected/movies1.tsv.f3,1.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f3_1_reorder() -> TestResult {
+    run(
+        &[TSV, "-f", "3,1", "--reorder"],
+        "tests/expected/movies1.tsv.f3,1.reorder.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f3_1_reorder_posix() -> TestResult {
+    run(
+        &[TSV, "-f", "3,1", "--reorder", "--posix"],
+        "tests/expected/movies1.tsv.f3,1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b3_1_2_posix() -> TestResult {
+    run(&[TSV, "-b", "3,1-2"], "tests/expected/movies1.tsv.b1-3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b3_1_2_reorder() -> TestResult {
+    run(
+        &[TSV, "-b", "3,1-2", "--reorder"],
+        "tests/expected/movies1.tsv.b3,1-2.reorder.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c2_3_1_2_posix() -> TestResult {
+    run(
+        &[TSV, "-c", "2-3,1-2"],
+        "tests/expected/movies1.tsv.c1-3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c2_3_1_2_reorder() -> TestResult {
+    run(
+        &[TSV, "-c", "2-3,1-2", "--reorder"],
+        "tests/expected/movies1.tsv.c2-3,1-2.reorder.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_field_names_posix() -> TestResult {
+    run(
+        &[CSV, "--header", "-F", "year,title", "-d", ","],
+        "tests/expected/movies1.csv.f1-2.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multi_char_delimiter() -> TestResult {
+    run(
+        &["tests/inputs/movies1.dcolons.txt", "-d", "::", "-f", "3,1"],
+        "tests/expected/movies1.dcolons.f1,3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_delimiter() -> TestResult {
+    run(
+        &[
+            "tests/inputs/aligned.txt",
+            "--regex-delimiter",
+            r"\s+",
+            "-f",
+            "1,3",
+        ],
+        "tests/expected/aligned.f1,3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_delimiter_output_delimiter() -> TestResult {
+    run(
+        &[
+            "tests/inputs/aligned.txt",
+            "--regex-delimiter",
+            r"\s+",
+            "-f",
+            "2-",
+            "--output-delimiter",
+            ",",
+        ],
+        "tests/expected/aligned.f2-.ocomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_delimiter_right_aligned() -> TestResult {
+    run(
+        &[
+            "tests/inputs/right-aligned.txt",
+            "--regex-delimiter",
+            r"\s+",
+            "-f",
+            "1,3",
+            "--output-delimiter",
+            ",",
+        ],
+        "tests/expected/right-aligned.f1,3.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_multi_char_output_delimiter() -> TestResult {
+    run(
+        &[CSV, "-d", ",", "-f", "1,3", "--output-delimiter", " | "],
+        "tests/expected/movies1.csv.f1,3.opipe.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_to_csv_output_delimiter() -> TestResult {
+    run(
+        &[BOOKS, "-f", "3,1", "--output-delimiter", ","],
+        "tests/expected/books.f1,3.ocomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_keeps_quotes() -> TestResult {
+    run(
+        &["tests/inputs/quotes.tsv", "-f", "2,3", "--raw"],
+        "tests/expected/quotes.f2,3.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_overrides_raw() -> TestResult {
+    run(
+        &[CSV, "-f", "2", "-d", ",", "--raw", "--csv"],
+        "tests/expected/movies1.csv.f2.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_does_not_quote_output() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([BOOKS, "-f", "3", "--raw", "--output-delimiter", ","])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\n20,000 Leagues Under the Sea\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_csv_multi_char_delimiter() -> TestResult {
+    dies(
+        &[CSV, "-f", "1", "-d", "::", "--csv"],
+        "--csv needs a one byte --delimiter",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn prints_undelimited_lines() -> TestResult {
+    run(
+        &[
+            "tests/inputs/ragged.csv",
+            "-d",
+            ",",
+            "-f",
+            "1,3",
+            "--flexible",
+        ],
+        "tests/expected/ragged.f1,3.flexible.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn only_delimited() -> TestResult {
+    run(
+        &[
+            "tests/inputs/ragged.csv",
+            "-d",
+            ",",
+            "-f",
+            "1,3",
+            "--flexible",
+            "-s",
+        ],
+        "tests/expected/ragged.f1,3.flexible.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_ragged_rows() -> TestResult {
+    let expected = fs::read_to_string("tests/expected/ragged.f1,3.out")?;
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/ragged.csv", "-d", ",", "-f", "1,3"])
+        .assert()
+        .success()
+        .stdout(expected)
+        .stderr(predicate::str::contains(
+            "ragged.csv: skipped row 4 with 2 fields instead of 3, see --flexible",
+        ))
+        .stderr(predicate::str::contains(
+            "ragged.csv: skipped row 5 with 4 fields instead of 3, see --flexible",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_ragged_rows_only_delimited() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/ragged.csv", "-d", ",", "-f", "1,3", "-s"])
+        .assert()
+        .success()
+        .stdout(fs::read_to_string("tests/expected/ragged.f1,3.s.out")?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_keeps_ragged_rows() -> TestResult {
+    run(
+        &["tests/inputs/ragged.csv", "-d", ",", "-f", "1,3", "--raw"],
+        "tests/expected/ragged.f1,3.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn graphemes() -> TestResult {
+    run(
+        &["tests/inputs/graphemes.txt", "-c", "1-2", "--graphemes"],
+        "tests/expected/graphemes.c1-2.graphemes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn graphemes_open() -> TestResult {
+    run(
+        &["tests/inputs/graphemes.txt", "-c", "3-", "--graphemes"],
+        "tests/expected/graphemes.c3-.graphemes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn display_width() -> TestResult {
+    run(
+        &["tests/inputs/wide.txt", "-c", "1-4", "--display-width"],
+        "tests/expected/wide.c1-4.display-width.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn display_width_splits_wide_char() -> TestResult {
+    run(
+        &["tests/inputs/wide.txt", "-c", "2-4", "--display-width"],
+        "tests/expected/wide.c2-4.display-width.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn display_width_open() -> TestResult {
+    run(
+        &["tests/inputs/wide.txt", "-c", "5-", "--display-width"],
+        "tests/expected/wide.c5-.display-width.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_graphemes_display_width() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([CSV, "-c", "1", "--graphemes", "--display-width"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_graphemes_without_chars() -> TestResult {
+    dies(&[CSV, "-f", "1", "--graphemes"], "--graphemes")
+}
+
+// --------------------------------------------------
+#[test]
+fn whole_chars_leaves_out_split_char() -> TestResult {
+    run(&[BOOKS, "-b", "1", "-n"], "tests/expected/books.b1.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn whole_chars_keeps_selected_char() -> TestResult {
+    run(&[BOOKS, "-b", "2", "-n"], "tests/expected/books.b2.n.out")
+}
+
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> TestResult {
+    let expected = fs::read(expected_file)?;
+    Command::cargo_bin(PRG)?
+        .args(args)
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_bytes() -> TestResult {
+    run_bytes(
+        &[BOOKS, "-b", "1", "--raw-bytes"],
+        "tests/expected/books.b1.raw-bytes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn raw_bytes_not_utf8() -> TestResult {
+    run_bytes(
+        &["tests/inputs/latin1.txt", "-b", "1-4", "--raw-bytes"],
+        "tests/expected/latin1.b1-4.raw-bytes.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn bytes_cuts_lines_after_bad_utf8() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/latin1.txt", "-b", "1-2"])
+        .assert()
+        .success()
+        .stdout("ca\nok\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn chars_cuts_lines_after_bad_utf8() -> TestResult {
+    for args in [
+        &["-c", "1"][..],
+        &["-c", "1", "--graphemes"],
+        &["-c", "1", "--display-width"],
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .write_stdin(b"abc\n\xe9t\xe9\nxyz\n".as_slice())
+            .assert()
+            .success()
+            .stdout("a\n\u{fffd}\nx\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fields_cuts_rows_after_bad_utf8() -> TestResult {
+    for args in [&["-f", "2"][..], &["-f", "2", "--raw"]] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .write_stdin(b"a\tb\n\xe9t\xe9\tx\xe9\nc\td\n".as_slice())
+            .assert()
+            .success()
+            .stdout("b\nx\u{fffd}\nd\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn whole_chars_range_starting_inside_char() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-b", "3", "-n"])
+        .write_stdin("aÉb\n")
+        .assert()
+        .success()
+        .stdout("É\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_whole_chars_without_bytes() -> TestResult {
+    dies(&[CSV, "-c", "1", "-n"], "--whole-chars")
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_json() -> TestResult {
+    run(
+        &[
+            CSV,
+            "-d",
+            ",",
+            "--header",
+            "-f",
+            "1,2",
+            "--output-format",
+            "json",
+        ],
+        "tests/expected/movies1.csv.f1-2.header.json.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_json_no_rows() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-d", ",", "--header", "-f", "1", "--output-format", "json"])
+        .write_stdin("title\n")
+        .assert()
+        .success()
+        .stdout("[]\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_jsonl() -> TestResult {
+    run(
+        &[
+            CSV,
+            "-d",
+            ",",
+            "--header",
+            "-F",
+            "dir*,title",
+            "--output-format",
+            "jsonl",
+        ],
+        "tests/expected/movies1.csv.Fdirector,title.header.jsonl.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_markdown() -> TestResult {
+    run(
+        &[
+            CSV,
+            "-d",
+            ",",
+            "--header",
+            "-f",
+            "1,2",
+            "--output-format",
+            "markdown",
+        ],
+        "tests/expected/movies1.csv.f1-2.header.markdown.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_json_two_files() -> TestResult {
+    let status = "tests/inputs/status.csv";
+    Command::cargo_bin(PRG)?
+        .args([status, status, "-d", ",", "--header", "-f", "1"])
+        .args(["--output-format", "json"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "[\n",
+            "  {\"name\":\"alpha\"},\n",
+            "  {\"name\":\"beta\"},\n",
+            "  {\"name\":\"gamma\"},\n",
+            "  {\"name\":\"delta\"},\n",
+            "  {\"name\":\"alpha\"},\n",
+            "  {\"name\":\"beta\"},\n",
+            "  {\"name\":\"gamma\"},\n",
+            "  {\"name\":\"delta\"}\n",
+            "]\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_markdown_two_files() -> TestResult {
+    let status = "tests/inputs/status.csv";
+    Command::cargo_bin(PRG)?
+        .args([status, status, "-d", ",", "--header", "-f", "1"])
+        .args(["--output-format", "markdown"])
+        .assert()
+        .success()
+        .stdout(concat!(
+            "| name |\n|---|\n",
+            "| alpha |\n| beta |\n| gamma |\n| delta |\n",
+            "| alpha |\n| beta |\n| gamma |\n| delta |\n",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_markdown_without_header() -> TestResult {
+    run(
+        &[TSV, "-f", "3,1", "--output-format", "markdown"],
+        "tests/expected/movies1.tsv.f1,3.markdown.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_markdown_escapes_pipes() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["-f", "2", "--output-format", "markdown"])
+        .write_stdin("a\tb|c\n")
+        .assert()
+        .success()
+        .stdout("| 2 |\n|---|\n| b\\|c |\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn output_format_csv() -> TestResult {
+    run(
+        &[BOOKS, "-f", "1,3", "--output-format", "csv"],
+        "tests/expected/books.f1,3.csv.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_output_format_output_delimiter() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args([
+            CSV,
+            "-f",
+            "1",
+            "--output-format",
+            "csv",
+            "--output-delimiter",
+            ";",
+        ])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn where_number() -> TestResult {
+    run(
+        &[
+            "tests/inputs/status.csv",
+            "-d",
+            ",",
+            "--header",
+            "-f",
+            "1,3",
+            "--where",
+            "col3 > 100",
+        ],
+        "tests/expected/status.f1,3.where-col3-gt-100.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn where_name_text_and_regex() -> TestResult {
+    run(
+        &[
+            "tests/inputs/status.csv",
+            "-d",
+            ",",
+            "--header",
+            "-f",
+            "1,3",
+            "--where",
+            "status == \"ok\"",
+            "--where",
+            "name ~ /^[ag]/",
+        ],
+        "tests/expected/status.f1,3.where-ok-regex.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn where_without_header() -> TestResult {
+    run(
+        &[
+            "tests/inputs/status.csv",
+            "-d",
+            ",",
+            "-f",
+            "1",
+            "--where",
+            "col2 != ok",
+        ],
+        "tests/expected/status.f1.where-col2-ne-ok.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_where_unknown_name() -> TestResult {
+    dies(
+        &[
+            "tests/inputs/status.csv",
+            "-d",
+            ",",
+            "--header",
+            "-f",
+            "1",
+            "--where",
+            "stat == ok",
+        ],
+        "unknown field name: stat (the headers are: name, status, size)",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_where_name_without_header() -> TestResult {
+    dies(
+        &[CSV, "-d", ",", "-f", "1", "--where", "title == x"],
+        "needs --header",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_where() -> TestResult {
+    dies(
+        &[CSV, "-f", "1", "--where", "col0 > 1"],
+        "invalid --where expression 'col0 > 1'",
+    )
 }
@@ -0,0 +1,69 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+const PRG: &str = env!("CARGO_BIN_EXE_findr");
+
+// --------------------------------------------------
+fn gen_tree(root: &Path, depth: usize, width: usize, files: usize) -> std::io::Result<()> {
+    for i in 0..files {
+        fs::write(root.join(format!("file{}.txt", i)), "findr")?;
+    }
+
+    if depth > 0 {
+        for i in 0..width {
+            let dir = root.join(format!("dir{}", i));
+            fs::create_dir(&dir)?;
+            gen_tree(&dir, depth - 1, width, files)?;
+        }
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn findr(args: &[&str]) {
+    let status = Command::new(PRG)
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .expect("failed to run findr");
+    assert!(status.success());
+}
+
+// --------------------------------------------------
+fn bench_walk(c: &mut Criterion) {
+    // Set FINDR_BENCH_DIR to measure a real tree (e.g. a network mount)
+    let tmp = TempDir::new().expect("failed to create temp dir");
+    let root = match std::env::var("FINDR_BENCH_DIR") {
+        Ok(dir) => dir,
+        Err(_) => {
+            gen_tree(tmp.path(), 4, 6, 10).expect("failed to generate tree");
+            tmp.path().to_string_lossy().to_string()
+        }
+    };
+
+    let mut group = c.benchmark_group("walk");
+    group.sample_size(20);
+
+    group.bench_function("sequential", |b| b.iter(|| findr(&[&root])));
+    for threads in ["2", "4", "8"] {
+        group.bench_with_input(BenchmarkId::new("threads", threads), threads, |b, n| {
+            b.iter(|| findr(&[&root, "--threads", n]))
+        });
+    }
+    group.bench_function("sequential/sorted", |b| {
+        b.iter(|| findr(&[&root, "--sort", "name"]))
+    });
+    group.bench_function("threads/4/sorted", |b| {
+        b.iter(|| findr(&[&root, "--threads", "4", "--sort", "name"]))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_walk);
+criterion_main!(benches);
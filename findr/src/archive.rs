@@ -0,0 +1,219 @@
+use crate::entry::Entry;
+use chrono::{NaiveDate, TimeZone, Utc};
+use flate2::read::GzDecoder;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+/// The kinds of archives that are searched
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl Format {
+    /// The format of `entry`, told by its extension, if it is an archive
+    pub(crate) fn of(entry: &Entry) -> Option<Self> {
+        if !entry.file_type().is_file() {
+            return None;
+        }
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// What an archive member is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Dir,
+    File,
+    Link,
+    Other,
+}
+
+/// An entry stored in an archive, with the path `archive!/inner/path`
+#[derive(Debug, Clone)]
+pub(crate) struct Member {
+    pub(crate) path: PathBuf,
+    pub(crate) name: String,
+    pub(crate) kind: Kind,
+    pub(crate) size: u64,
+    pub(crate) mode: u32,
+    pub(crate) uid: Option<u64>,
+    pub(crate) gid: Option<u64>,
+    pub(crate) mtime: Option<i64>,
+    pub(crate) target: Option<PathBuf>,
+    pub(crate) depth: usize,
+}
+
+/// The path of a member inside its archive, without `./` in front or `/`
+/// at the end
+fn trim(inner: &str) -> &str {
+    inner.trim_start_matches("./").trim_end_matches('/')
+}
+
+impl Member {
+    /// The member at `inner` in `archive`, or `None` for the archive's root
+    fn new(archive: &Path, depth: usize, inner: &str, kind: Kind) -> Option<Self> {
+        let inner = trim(inner);
+        if inner.is_empty() || inner == "." {
+            return None;
+        }
+
+        Some(Member {
+            path: PathBuf::from(format!("{}!/{}", archive.display(), inner)),
+            name: inner.rsplit('/').next().unwrap_or(inner).to_string(),
+            kind,
+            size: 0,
+            mode: 0,
+            uid: None,
+            gid: None,
+            mtime: None,
+            target: None,
+            depth: depth + inner.split('/').filter(|part| !part.is_empty()).count(),
+        })
+    }
+}
+
+/// Archives may leave out the entries of directories and only hold the
+/// files in them. Those directories are made up from the paths of the
+/// members, once, just before the first member inside them.
+struct Dirs {
+    archive: PathBuf,
+    depth: usize,
+    seen: HashSet<String>,
+}
+
+impl Dirs {
+    fn new(archive: &Path, depth: usize) -> Self {
+        Dirs {
+            archive: archive.to_path_buf(),
+            depth,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Visits the directories above `inner` that weren't seen yet. Returns
+    /// false when `inner` is a directory that was already visited.
+    fn visit_parents(
+        &mut self,
+        inner: &str,
+        kind: Kind,
+        visit: &mut impl FnMut(Member, &mut dyn Read),
+    ) -> bool {
+        let inner = trim(inner);
+        for (i, _) in inner.match_indices('/') {
+            let dir = &inner[..i];
+            if self.seen.insert(dir.to_string()) {
+                if let Some(member) = Member::new(&self.archive, self.depth, dir, Kind::Dir) {
+                    visit(member, &mut io::empty());
+                }
+            }
+        }
+        kind != Kind::Dir || self.seen.insert(inner.to_string())
+    }
+}
+
+/// Hands every member of the archive at `path`, `depth` levels below its
+/// search path, to `visit` along with its content
+pub(crate) fn search(
+    path: &Path,
+    depth: usize,
+    format: Format,
+    mut visit: impl FnMut(Member, &mut dyn Read),
+) -> io::Result<()> {
+    let file = BufReader::new(File::open(path)?);
+    match format {
+        Format::Tar => search_tar(path, depth, file, visit),
+        Format::TarGz => search_tar(path, depth, GzDecoder::new(file), visit),
+        Format::Zip => {
+            let mut zip = zip::ZipArchive::new(file)?;
+            let mut dirs = Dirs::new(path, depth);
+            for i in 0..zip.len() {
+                let mut file = zip.by_index(i)?;
+                let kind = match file.unix_mode().map(|mode| mode & 0o170000) {
+                    _ if file.is_dir() => Kind::Dir,
+                    Some(0o120000) => Kind::Link,
+                    _ => Kind::File,
+                };
+
+                if !dirs.visit_parents(file.name(), kind, &mut visit) {
+                    continue;
+                }
+                let Some(mut member) = Member::new(path, depth, file.name(), kind) else {
+                    continue;
+                };
+                let modified = file.last_modified();
+                member.size = file.size();
+                member.mode = file.unix_mode().unwrap_or(0);
+                member.mtime = NaiveDate::from_ymd_opt(
+                    modified.year().into(),
+                    modified.month().into(),
+                    modified.day().into(),
+                )
+                .and_then(|date| {
+                    date.and_hms_opt(
+                        modified.hour().into(),
+                        modified.minute().into(),
+                        modified.second().into(),
+                    )
+                })
+                .map(|time| Utc.from_utc_datetime(&time).timestamp());
+                if kind == Kind::Link {
+                    let mut target = String::new();
+                    file.read_to_string(&mut target)?;
+                    member.target = Some(PathBuf::from(target));
+                }
+                visit(member, &mut file);
+            }
+            Ok(())
+        }
+    }
+}
+
+fn search_tar(
+    path: &Path,
+    depth: usize,
+    reader: impl Read,
+    mut visit: impl FnMut(Member, &mut dyn Read),
+) -> io::Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    let mut dirs = Dirs::new(path, depth);
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            tar::EntryType::Directory => Kind::Dir,
+            tar::EntryType::Regular | tar::EntryType::Continuous => Kind::File,
+            tar::EntryType::Symlink | tar::EntryType::Link => Kind::Link,
+            _ => Kind::Other,
+        };
+
+        let inner = entry.path()?.to_string_lossy().to_string();
+        if !dirs.visit_parents(&inner, kind, &mut visit) {
+            continue;
+        }
+        let Some(mut member) = Member::new(path, depth, &inner, kind) else {
+            continue;
+        };
+        member.size = header.size()?;
+        member.mode = header.mode()?;
+        member.uid = header.uid().ok();
+        member.gid = header.gid().ok();
+        member.mtime = header.mtime().ok().map(|mtime| mtime as i64);
+        member.target = entry.link_name()?.map(|target| target.to_path_buf());
+        visit(member, &mut entry);
+    }
+    Ok(())
+}
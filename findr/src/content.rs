@@ -0,0 +1,65 @@
+use regex::bytes::Regex;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// How much of a file is inspected to tell binary from text and to sniff
+/// its type
+pub(crate) const SNIFF_LEN: usize = 8192;
+
+/// Whether any line of the file at `path` matches `re`. The file is read
+/// one line at a time and binary files (with a NUL byte near the start)
+/// never match.
+pub(crate) fn contains(path: &Path, re: &Regex) -> io::Result<bool> {
+    contains_in(File::open(path)?, re)
+}
+
+/// Like `contains`, for content that isn't a file of its own
+pub(crate) fn contains_in(content: impl Read, re: &Regex) -> io::Result<bool> {
+    let mut reader = BufReader::with_capacity(SNIFF_LEN, content);
+    if is_binary(reader.fill_buf()?) {
+        return Ok(false);
+    }
+
+    let mut line = vec![];
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(false);
+        }
+        if re.is_match(&line) {
+            return Ok(true);
+        }
+    }
+}
+
+/// The MIME type of the file at `path`, sniffed from its magic bytes
+pub(crate) fn mime_type(path: &Path) -> io::Result<&'static str> {
+    mime_type_of(File::open(path)?)
+}
+
+/// Like `mime_type`, for content that isn't a file of its own
+pub(crate) fn mime_type_of(content: impl Read) -> io::Result<&'static str> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    content.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+
+    Ok(match infer::get(&head) {
+        Some(kind) => kind.mime_type(),
+        None if head.is_empty() => "inode/x-empty",
+        None if is_binary(&head) => "application/octet-stream",
+        None => "text/plain",
+    })
+}
+
+/// Whether `mime` is selected by `pattern`, either a full `type/subtype` or
+/// a `type/*` wildcard
+pub(crate) fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => mime.split('/').next() == Some(prefix),
+        None => pattern.eq_ignore_ascii_case(mime),
+    }
+}
+
+fn is_binary(head: &[u8]) -> bool {
+    head.contains(&0)
+}
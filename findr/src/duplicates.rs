@@ -0,0 +1,145 @@
+use crate::entry::Entry;
+use crate::error::{FindError, Reporter};
+use crate::paths::PathFormat;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, Metadata};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+/// How much of each file is hashed before hashing candidates in full
+const PARTIAL_LEN: u64 = 4096;
+
+/// Regular files with the same content
+#[derive(Debug, Serialize)]
+pub(crate) struct DuplicateSet {
+    size: u64,
+    hash: String,
+    paths: Vec<PathBuf>,
+}
+
+/// Groups the regular files in `entries` by size, then by the hash of their
+/// first bytes and finally by the hash of their whole content, so only
+/// files that could be duplicates are read in full. Empty files are skipped,
+/// and a file reached by several paths, through hard links or followed
+/// symlinks, is only counted once under its first path.
+pub(crate) fn find(entries: Vec<Entry>, reporter: &Reporter) -> Vec<DuplicateSet> {
+    let mut files: BTreeMap<FileId, (u64, PathBuf)> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.file_type().is_file()) {
+        match entry.metadata() {
+            Err(err) => reporter.report(FindError::io(entry.path(), err)),
+            Ok(metadata) if metadata.len() == 0 => {}
+            Ok(metadata) => {
+                let id = file_id(&metadata, entry.path());
+                let path = entry.path().to_path_buf();
+                match files.get(&id) {
+                    Some((_, first)) if *first <= path => {}
+                    _ => {
+                        files.insert(id, (metadata.len(), path));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut by_size: BTreeMap<u64, BTreeSet<PathBuf>> = BTreeMap::new();
+    for (size, path) in files.into_values() {
+        by_size.entry(size).or_default().insert(path);
+    }
+
+    let mut sets = vec![];
+    for (size, paths) in by_size.into_iter().filter(|(_, paths)| paths.len() > 1) {
+        for (hash, paths) in group_by_hash(paths, Some(PARTIAL_LEN), reporter) {
+            let groups = if size <= PARTIAL_LEN {
+                vec![(hash, paths)]
+            } else {
+                group_by_hash(paths, None, reporter)
+            };
+
+            sets.extend(groups.into_iter().map(|(hash, paths)| DuplicateSet {
+                size,
+                hash,
+                paths: paths.into_iter().collect(),
+            }));
+        }
+    }
+
+    sets.sort_by(|a, b| a.paths.cmp(&b.paths));
+    sets
+}
+
+/// Tells files apart: the device and inode where there are some, or else
+/// the path
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum FileId {
+    #[cfg(unix)]
+    Inode(u64, u64),
+    #[cfg(not(unix))]
+    Path(PathBuf),
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata, _path: &Path) -> FileId {
+    use std::os::unix::fs::MetadataExt;
+    FileId::Inode(metadata.dev(), metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata, path: &Path) -> FileId {
+    FileId::Path(path.to_path_buf())
+}
+
+pub(crate) fn print(
+    sets: &[DuplicateSet],
+    json: bool,
+    paths: &PathFormat,
+) -> serde_json::Result<()> {
+    for (i, set) in sets.iter().enumerate() {
+        let formatted: Vec<_> = set.paths.iter().map(|path| paths.format(path)).collect();
+        if json {
+            let set = DuplicateSet {
+                size: set.size,
+                hash: set.hash.clone(),
+                paths: formatted.into_iter().map(PathBuf::from).collect(),
+            };
+            println!("{}", serde_json::to_string(&set)?);
+        } else {
+            if i > 0 {
+                println!();
+            }
+            formatted.iter().for_each(|path| println!("{}", path));
+        }
+    }
+    Ok(())
+}
+
+/// Hashes up to `limit` bytes of each file, keeping the groups with more
+/// than one file
+fn group_by_hash(
+    paths: BTreeSet<PathBuf>,
+    limit: Option<u64>,
+    reporter: &Reporter,
+) -> Vec<(String, BTreeSet<PathBuf>)> {
+    let mut by_hash: BTreeMap<String, BTreeSet<PathBuf>> = BTreeMap::new();
+    for path in paths {
+        match hash_file(&path, limit) {
+            Err(err) => reporter.report(FindError::io(&path, err)),
+            Ok(hash) => {
+                by_hash.entry(hash).or_default().insert(path);
+            }
+        }
+    }
+
+    by_hash
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect()
+}
+
+fn hash_file(path: &Path, limit: Option<u64>) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut file = File::open(path)?.take(limit.unwrap_or(u64::MAX));
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
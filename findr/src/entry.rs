@@ -0,0 +1,125 @@
+use std::ffi::OsStr;
+use std::fs::{self, FileType, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// When symbolic links are followed during a walk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Follow {
+    /// Never follow links (`-P`)
+    Never,
+    /// Follow links given as search paths (`-H`)
+    Roots,
+    /// Follow all links (`-L`)
+    Always,
+}
+
+/// A filesystem entry found by one of the walkers
+#[derive(Debug, Clone)]
+pub(crate) struct Entry {
+    path: PathBuf,
+    file_type: FileType,
+    depth: usize,
+    path_is_symlink: bool,
+}
+
+impl Entry {
+    pub(crate) fn new(path: PathBuf, file_type: FileType, depth: usize) -> Self {
+        Entry {
+            path,
+            file_type,
+            depth,
+            path_is_symlink: file_type.is_symlink(),
+        }
+    }
+
+    /// Looks up the entry for `path`, resolving it when it is a symlink and
+    /// `follow` is set. Broken symlinks are kept as links.
+    pub(crate) fn from_path(path: PathBuf, depth: usize, follow: bool) -> io::Result<Self> {
+        let file_type = fs::symlink_metadata(&path)?.file_type();
+        if !(follow && file_type.is_symlink()) {
+            return Ok(Entry::new(path, file_type, depth));
+        }
+
+        Ok(Entry {
+            file_type: fs::metadata(&path).map_or(file_type, |m| m.file_type()),
+            path,
+            depth,
+            path_is_symlink: true,
+        })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    pub(crate) fn file_name(&self) -> &OsStr {
+        self.path
+            .file_name()
+            .unwrap_or_else(|| self.path.as_os_str())
+    }
+
+    pub(crate) fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub(crate) fn path_is_symlink(&self) -> bool {
+        self.path_is_symlink
+    }
+
+    pub(crate) fn is_broken_link(&self) -> bool {
+        self.path_is_symlink && fs::metadata(&self.path).is_err()
+    }
+
+    #[cfg(unix)]
+    pub(crate) fn is_executable(&self) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        self.metadata()
+            .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0)
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn is_executable(&self) -> bool {
+        false
+    }
+
+    /// Whether the entry is a regular file with no content or a directory
+    /// with no entries
+    pub(crate) fn is_empty(&self) -> bool {
+        if self.file_type.is_dir() {
+            fs::read_dir(&self.path).is_ok_and(|mut entries| entries.next().is_none())
+        } else if self.file_type.is_file() {
+            self.metadata().is_ok_and(|metadata| metadata.len() == 0)
+        } else {
+            false
+        }
+    }
+
+    /// The metadata of the entry, or of its target if it was followed
+    pub(crate) fn metadata(&self) -> io::Result<Metadata> {
+        if self.path_is_symlink && !self.file_type.is_symlink() {
+            fs::metadata(&self.path)
+        } else {
+            fs::symlink_metadata(&self.path)
+        }
+    }
+}
+
+impl From<walkdir::DirEntry> for Entry {
+    fn from(entry: walkdir::DirEntry) -> Self {
+        Entry {
+            file_type: entry.file_type(),
+            depth: entry.depth(),
+            path_is_symlink: entry.path_is_symlink(),
+            path: entry.into_path(),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// An error found while searching a path
+#[derive(Debug)]
+pub enum FindError {
+    /// `path` couldn't be read
+    Io { path: PathBuf, err: io::Error },
+    /// Following the link at `path` leads back to `ancestor`
+    Loop { path: PathBuf, ancestor: PathBuf },
+}
+
+impl FindError {
+    pub(crate) fn io(path: &Path, err: io::Error) -> Self {
+        FindError::Io {
+            path: path.to_path_buf(),
+            err,
+        }
+    }
+
+    /// The path the error was found at
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Io { path, .. } | Self::Loop { path, .. } => path,
+        }
+    }
+
+    /// A stable name for the kind of error, for scripts to match on
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Loop { .. } => "loop",
+            Self::Io { err, .. } => match err.kind() {
+                io::ErrorKind::NotFound => "not-found",
+                io::ErrorKind::PermissionDenied => "permission-denied",
+                _ => "io",
+            },
+        }
+    }
+}
+
+impl fmt::Display for FindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, err } => write!(f, "{}: {}", path.display(), err),
+            Self::Loop { path, ancestor } => write!(
+                f,
+                "File system loop detected; '{}' is part of the same file system loop as '{}'.",
+                path.display(),
+                ancestor.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FindError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { err, .. } => Some(err),
+            Self::Loop { .. } => None,
+        }
+    }
+}
+
+impl From<walkdir::Error> for FindError {
+    fn from(err: walkdir::Error) -> Self {
+        let path = err.path().unwrap_or_else(|| Path::new("")).to_path_buf();
+        match err.loop_ancestor() {
+            Some(ancestor) => FindError::Loop {
+                ancestor: ancestor.to_path_buf(),
+                path,
+            },
+            None => FindError::Io {
+                err: err
+                    .into_io_error()
+                    .expect("walkdir errors are either loops or I/O errors"),
+                path,
+            },
+        }
+    }
+}
+
+/// Prints errors one per line as they are found, and remembers whether
+/// there were any so the exit status can report a partial search
+#[derive(Debug)]
+pub(crate) struct Reporter {
+    quiet: bool,
+    failed: AtomicBool,
+}
+
+impl Reporter {
+    pub(crate) fn new(quiet: bool) -> Self {
+        Reporter {
+            quiet,
+            failed: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn report(&self, err: FindError) {
+        self.failed.store(true, Ordering::Relaxed);
+        if !self.quiet {
+            eprintln!("findr: {}: {}", err.kind(), err);
+        }
+    }
+
+    pub(crate) fn failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+}
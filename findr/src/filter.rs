@@ -0,0 +1,205 @@
+use crate::archive::{Kind, Member};
+use crate::content;
+use crate::entry::Entry;
+use crate::error::FindError;
+use clap::builder::PossibleValue;
+use clap::ValueEnum;
+use regex::Regex;
+use std::ffi::OsStr;
+use std::io::{self, Read};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+
+/// A kind of entry to select, as given to `--type`
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum EntryType {
+    Dir,
+    File,
+    Link,
+    BrokenLink,
+    Socket,
+    Fifo,
+    BlockDevice,
+    CharDevice,
+    Executable,
+    Empty,
+}
+
+impl ValueEnum for EntryType {
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        match self {
+            Self::Dir => Some(PossibleValue::new("d").help("directory")),
+            Self::File => Some(PossibleValue::new("f").help("regular file")),
+            Self::Link => Some(PossibleValue::new("l").help("symbolic link")),
+            Self::BrokenLink => Some(PossibleValue::new("o").help("broken (orphan) symlink")),
+            Self::Socket => Some(PossibleValue::new("s").help("socket")),
+            Self::Fifo => Some(PossibleValue::new("p").help("named pipe (FIFO)")),
+            Self::BlockDevice => Some(PossibleValue::new("b").help("block device")),
+            Self::CharDevice => Some(PossibleValue::new("c").help("character device")),
+            Self::Executable => Some(PossibleValue::new("x").help("executable file")),
+            Self::Empty => Some(PossibleValue::new("e").help("empty file or directory")),
+        }
+    }
+
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Dir,
+            Self::File,
+            Self::Link,
+            Self::BrokenLink,
+            Self::Socket,
+            Self::Fifo,
+            Self::BlockDevice,
+            Self::CharDevice,
+            Self::Executable,
+            Self::Empty,
+        ]
+    }
+}
+
+impl EntryType {
+    fn matches(&self, entry: &Entry) -> bool {
+        let file_type = entry.file_type();
+        match self {
+            Self::Dir => file_type.is_dir(),
+            Self::File => file_type.is_file(),
+            Self::Link => file_type.is_symlink(),
+            Self::BrokenLink => entry.is_broken_link(),
+            #[cfg(unix)]
+            Self::Socket => file_type.is_socket(),
+            #[cfg(unix)]
+            Self::Fifo => file_type.is_fifo(),
+            #[cfg(unix)]
+            Self::BlockDevice => file_type.is_block_device(),
+            #[cfg(unix)]
+            Self::CharDevice => file_type.is_char_device(),
+            #[cfg(not(unix))]
+            Self::Socket | Self::Fifo | Self::BlockDevice | Self::CharDevice => false,
+            Self::Executable => file_type.is_file() && entry.is_executable(),
+            Self::Empty => entry.is_empty(),
+        }
+    }
+}
+
+/// The predicates an entry has to pass to be printed
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Filter {
+    pub(crate) names: Vec<Regex>,
+    pub(crate) entry_types: Vec<EntryType>,
+    pub(crate) contains: Option<regex::bytes::Regex>,
+    pub(crate) mimes: Vec<String>,
+    pub(crate) empty: bool,
+}
+
+impl Filter {
+    /// Whether `entry` passes every predicate, failing only if the file had
+    /// to be read and couldn't be
+    pub(crate) fn is_match(&self, entry: &Entry) -> Result<bool, FindError> {
+        let matches = (self.entry_types.is_empty()
+            || self.entry_types.iter().any(|et| et.matches(entry)))
+            && self.is_name_match(entry.file_name())
+            && (!self.empty || entry.is_empty());
+
+        if matches {
+            self.is_content_match(entry)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Whether an entry that was just deleted passed the predicates. Only
+    /// its name and whether it was a directory are left to check, so every
+    /// type other than directories is taken to match any other entry.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn is_deleted_match(&self, name: &OsStr, is_dir: bool) -> bool {
+        (self.entry_types.is_empty()
+            || self
+                .entry_types
+                .iter()
+                .any(|et| (*et == EntryType::Dir) == is_dir))
+            && self.is_name_match(name)
+    }
+
+    /// Whether a member of an archive passes every predicate, reading its
+    /// `content` only when needed
+    pub(crate) fn is_member_match(
+        &self,
+        member: &Member,
+        content: &mut dyn Read,
+    ) -> io::Result<bool> {
+        let is_file = member.kind == Kind::File;
+        let is_type = |et: &EntryType| match et {
+            EntryType::Dir => member.kind == Kind::Dir,
+            EntryType::File => is_file,
+            EntryType::Link => member.kind == Kind::Link,
+            EntryType::Executable => is_file && member.mode & 0o111 != 0,
+            EntryType::Empty => is_file && member.size == 0,
+            _ => false,
+        };
+        let matches = (self.entry_types.is_empty() || self.entry_types.iter().any(is_type))
+            && self.is_name_match(OsStr::new(&member.name))
+            && (!self.empty || is_file && member.size == 0);
+
+        if !matches || self.contains.is_none() && self.mimes.is_empty() {
+            return Ok(matches);
+        }
+        if !is_file {
+            return Ok(false);
+        }
+
+        let mut head = Vec::with_capacity(content::SNIFF_LEN);
+        content
+            .take(content::SNIFF_LEN as u64)
+            .read_to_end(&mut head)?;
+        if !self.mimes.is_empty() {
+            let mime = content::mime_type_of(head.as_slice())?;
+            if !self
+                .mimes
+                .iter()
+                .any(|pattern| content::mime_matches(pattern, mime))
+            {
+                return Ok(false);
+            }
+        }
+        match &self.contains {
+            Some(re) => content::contains_in(head.as_slice().chain(content), re),
+            None => Ok(true),
+        }
+    }
+
+    fn is_name_match(&self, name: &OsStr) -> bool {
+        self.names.is_empty()
+            || self
+                .names
+                .iter()
+                .any(|re| re.is_match(&name.to_string_lossy()))
+    }
+
+    /// Checks the predicates that need to read the file, after the cheaper ones
+    fn is_content_match(&self, entry: &Entry) -> Result<bool, FindError> {
+        if self.contains.is_none() && self.mimes.is_empty() {
+            return Ok(true);
+        }
+        if !entry.file_type().is_file() {
+            return Ok(false);
+        }
+
+        if !self.mimes.is_empty() {
+            let mime =
+                content::mime_type(entry.path()).map_err(|err| FindError::io(entry.path(), err))?;
+            if !self
+                .mimes
+                .iter()
+                .any(|pattern| content::mime_matches(pattern, mime))
+            {
+                return Ok(false);
+            }
+        }
+        match &self.contains {
+            Some(re) => {
+                content::contains(entry.path(), re).map_err(|err| FindError::io(entry.path(), err))
+            }
+            None => Ok(true),
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use crate::entry::{Entry, Follow};
+use crate::error::FindError;
+use crate::filter::{EntryType, Filter};
+use crate::gitignore::Skip;
+use crate::sort::SortKey;
+use regex::Regex;
+use std::ffi::OsStr;
+use std::fs::{self, FileType, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::vec;
+use walkdir::WalkDir;
+
+/// Assembles a search the same way the command line does, for use from Rust
+/// code
+///
+/// ```no_run
+/// use findr::{EntryType, FindBuilder};
+/// use regex::Regex;
+///
+/// let sources = FindBuilder::new("src")
+///     .entry_type(EntryType::File)
+///     .name(Regex::new(r"\.rs$").unwrap())
+///     .build();
+///
+/// for found in sources {
+///     match found {
+///         Ok(found) => println!("{}", found.path().display()),
+///         Err(err) => eprintln!("{}", err),
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FindBuilder {
+    paths: Vec<PathBuf>,
+    filter: Filter,
+    follow: Follow,
+    sort: Option<SortKey>,
+    reverse: bool,
+    respect_ignore: bool,
+    hidden: Option<bool>,
+}
+
+impl FindBuilder {
+    /// Starts a search below `path`
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        FindBuilder {
+            paths: vec![path.as_ref().to_path_buf()],
+            filter: Filter::default(),
+            follow: Follow::Never,
+            sort: None,
+            reverse: false,
+            respect_ignore: false,
+            hidden: None,
+        }
+    }
+
+    /// Also searches below `path`, after the paths already added
+    pub fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Selects entries whose file name matches `name`. Entries matching any
+    /// of the names are selected.
+    pub fn name(mut self, name: Regex) -> Self {
+        self.filter.names.push(name);
+        self
+    }
+
+    /// Selects entries of type `entry_type`. Entries of any of the types are
+    /// selected.
+    pub fn entry_type(mut self, entry_type: EntryType) -> Self {
+        self.filter.entry_types.push(entry_type);
+        self
+    }
+
+    /// Selects regular text files with a line matching `contains`
+    pub fn contains(mut self, contains: regex::bytes::Regex) -> Self {
+        self.filter.contains = Some(contains);
+        self
+    }
+
+    /// Selects regular files of MIME type `mime`, either `type/subtype` or
+    /// `type/*`. Files of any of the types are selected.
+    pub fn mime<S: Into<String>>(mut self, mime: S) -> Self {
+        self.filter.mimes.push(mime.into());
+        self
+    }
+
+    /// Selects only empty files and directories
+    pub fn empty(mut self, yes: bool) -> Self {
+        self.filter.empty = yes;
+        self
+    }
+
+    /// Sets which symbolic links are followed, none by default
+    pub fn follow(mut self, follow: Follow) -> Self {
+        self.follow = follow;
+        self
+    }
+
+    /// Visits the entries of each directory ordered by `key`
+    pub fn sort(mut self, key: SortKey) -> Self {
+        self.sort = Some(key);
+        self
+    }
+
+    /// Reverses the order set with `sort`
+    pub fn reverse(mut self, yes: bool) -> Self {
+        self.reverse = yes;
+        self
+    }
+
+    /// Skips what `.ignore` files exclude and, inside a git repository, what
+    /// `.gitignore` files, `.git/info/exclude` and the global git excludes
+    /// do. Hidden entries are then skipped too unless `hidden` says otherwise.
+    pub fn respect_ignore(mut self, yes: bool) -> Self {
+        self.respect_ignore = yes;
+        self
+    }
+
+    /// Whether entries whose name starts with a dot are found, by default
+    /// only when not respecting ignore files
+    pub fn hidden(mut self, yes: bool) -> Self {
+        self.hidden = Some(yes);
+        self
+    }
+
+    pub(crate) fn skip(&self) -> Skip {
+        let hidden = self.hidden.unwrap_or(!self.respect_ignore);
+        Skip::new(!hidden, self.respect_ignore)
+    }
+
+    pub(crate) fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Starts the search. Paths are walked one after the other, depth first.
+    pub fn build(self) -> Find {
+        Find {
+            skip: self.skip(),
+            paths: self.paths.into_iter(),
+            walker: None,
+            filter: self.filter,
+            follow: self.follow,
+            sort: self.sort,
+            reverse: self.reverse,
+        }
+    }
+}
+
+/// The entries found by a search, along with the errors that kept parts of
+/// it from being searched
+pub struct Find {
+    paths: vec::IntoIter<PathBuf>,
+    walker: Option<walkdir::IntoIter>,
+    filter: Filter,
+    skip: Skip,
+    follow: Follow,
+    sort: Option<SortKey>,
+    reverse: bool,
+}
+
+impl Find {
+    fn walker(&self, path: PathBuf) -> walkdir::IntoIter {
+        let follow = self.follow;
+        let mut walker = WalkDir::new(path)
+            .follow_links(follow == Follow::Always)
+            .follow_root_links(follow != Follow::Never);
+        if let Some(key) = self.sort {
+            let reverse = self.reverse;
+            walker = walker.sort_by(move |a, b| {
+                let ordering = key.compare(a.path(), b.path(), follow == Follow::Always);
+                if reverse {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            });
+        }
+        walker.into_iter()
+    }
+}
+
+impl Iterator for Find {
+    type Item = Result<Match, FindError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let walker = match &mut self.walker {
+                Some(walker) => walker,
+                None => {
+                    let path = self.paths.next()?;
+                    self.walker.insert(self.walker(path))
+                }
+            };
+
+            let entry = match walker.next() {
+                None => {
+                    self.walker = None;
+                    continue;
+                }
+                Some(entry) => entry
+                    .map(|entry| resolve_root(entry, self.follow))
+                    .or_else(|err| broken_link(err, self.follow)),
+            };
+
+            match entry {
+                Err(err) => return Some(Err(FindError::from(err))),
+                Ok(entry) if self.skip.skips(&entry) => {
+                    if entry.file_type().is_dir() {
+                        walker.skip_current_dir();
+                    }
+                }
+                Ok(entry) => match self.filter.is_match(&entry) {
+                    Ok(true) => return Some(Ok(Match { entry })),
+                    Ok(false) => {}
+                    Err(err) => return Some(Err(err)),
+                },
+            }
+        }
+    }
+}
+
+/// An entry that passed every predicate of a search
+#[derive(Debug, Clone)]
+pub struct Match {
+    entry: Entry,
+}
+
+impl Match {
+    pub fn path(&self) -> &Path {
+        self.entry.path()
+    }
+
+    pub fn file_name(&self) -> &OsStr {
+        self.entry.file_name()
+    }
+
+    /// The type of the entry, or of its target if it is a followed link
+    pub fn file_type(&self) -> FileType {
+        self.entry.file_type()
+    }
+
+    /// How far below the search path the entry is, 0 for the path itself
+    pub fn depth(&self) -> usize {
+        self.entry.depth()
+    }
+
+    pub fn path_is_symlink(&self) -> bool {
+        self.entry.path_is_symlink()
+    }
+
+    /// The metadata of the entry, or of its target if it is a followed link
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        self.entry.metadata()
+    }
+
+    pub fn into_path(self) -> PathBuf {
+        self.entry.into_path()
+    }
+
+    pub(crate) fn into_entry(self) -> Entry {
+        self.entry
+    }
+}
+
+/// `WalkDir` descends into root symlinks but still reports them as links
+/// unless every link is followed
+fn resolve_root(entry: walkdir::DirEntry, follow: Follow) -> Entry {
+    if follow == Follow::Roots && entry.depth() == 0 && entry.path_is_symlink() {
+        if let Ok(root) = Entry::from_path(entry.path().to_path_buf(), 0, true) {
+            return root;
+        }
+    }
+    Entry::from(entry)
+}
+
+/// Followed symlinks whose target doesn't exist are errors for `WalkDir`,
+/// but they are still entries to report
+fn broken_link(err: walkdir::Error, follow: Follow) -> Result<Entry, walkdir::Error> {
+    let is_broken = follow != Follow::Never
+        && err.io_error().map(io::Error::kind) == Some(io::ErrorKind::NotFound)
+        && err
+            .path()
+            .and_then(|path| fs::symlink_metadata(path).ok())
+            .is_some_and(|metadata| metadata.file_type().is_symlink());
+
+    match err.path() {
+        Some(path) if is_broken => {
+            Entry::from_path(path.to_path_buf(), err.depth(), false).map_err(|_| err)
+        }
+        _ => Err(err),
+    }
+}
@@ -0,0 +1,136 @@
+use crate::entry::Entry;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Decides which entries a walk skips: hidden ones, and with
+/// `--respect-ignore` the ones excluded by `.ignore` files and, inside a git
+/// repository, by `.gitignore` files, `.git/info/exclude` and the global
+/// git excludes
+#[derive(Debug)]
+pub(crate) struct Skip {
+    hidden: bool,
+    ignore: Option<IgnoreRules>,
+}
+
+impl Skip {
+    pub(crate) fn new(hidden: bool, respect_ignore: bool) -> Self {
+        Skip {
+            hidden,
+            ignore: respect_ignore.then(IgnoreRules::new),
+        }
+    }
+
+    /// Whether `entry` and, for a directory, everything below it is left out
+    /// of the walk. The search paths themselves are never skipped.
+    pub(crate) fn skips(&self, entry: &Entry) -> bool {
+        self.skips_path(entry.path(), entry.depth(), entry.file_type().is_dir())
+    }
+
+    /// Like `skips`, for an entry that may no longer exist
+    pub(crate) fn skips_path(&self, path: &Path, depth: usize, is_dir: bool) -> bool {
+        if depth == 0 {
+            return false;
+        }
+        let is_hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if self.hidden && is_hidden {
+            return true;
+        }
+        match &self.ignore {
+            Some(rules) => rules.is_ignored(path, is_dir),
+            None => false,
+        }
+    }
+}
+
+/// The ignore files of a directory
+#[derive(Debug)]
+struct DirRules {
+    ignore: Gitignore,
+    gitignore: Gitignore,
+    /// `.git/info/exclude` when the directory is the root of a repository
+    exclude: Option<Gitignore>,
+}
+
+#[derive(Debug)]
+struct IgnoreRules {
+    cwd: PathBuf,
+    global: Gitignore,
+    dirs: Mutex<HashMap<PathBuf, Arc<DirRules>>>,
+}
+
+impl IgnoreRules {
+    fn new() -> Self {
+        IgnoreRules {
+            cwd: env::current_dir().unwrap_or_default(),
+            global: Gitignore::global().0,
+            dirs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Checks `path` against the ignore files of its ancestors, the nearest
+    /// directory first, up to the root of the repository it is in
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path: PathBuf = self.cwd.join(path).components().collect();
+
+        let mut chain = vec![];
+        let mut in_repo = false;
+        for dir in path.ancestors().skip(1) {
+            let rules = self.dir_rules(dir);
+            chain.push(rules.clone());
+            if rules.exclude.is_some() {
+                in_repo = true;
+                break;
+            }
+        }
+
+        let mut matchers = vec![];
+        for rules in &chain {
+            matchers.push(&rules.ignore);
+            if in_repo {
+                matchers.push(&rules.gitignore);
+            }
+        }
+        if in_repo {
+            matchers.extend(chain.last().and_then(|rules| rules.exclude.as_ref()));
+            matchers.push(&self.global);
+        }
+
+        matchers
+            .into_iter()
+            .map(|matcher| matcher.matched(&path, is_dir))
+            .find(|matched| !matched.is_none())
+            .is_some_and(|matched| matched.is_ignore())
+    }
+
+    fn dir_rules(&self, dir: &Path) -> Arc<DirRules> {
+        let mut dirs = self.dirs.lock().unwrap_or_else(|err| err.into_inner());
+        dirs.entry(dir.to_path_buf())
+            .or_insert_with(|| {
+                let git_dir = dir.join(".git");
+                Arc::new(DirRules {
+                    ignore: load(dir, dir.join(".ignore")),
+                    gitignore: load(dir, dir.join(".gitignore")),
+                    exclude: git_dir
+                        .exists()
+                        .then(|| load(dir, git_dir.join("info").join("exclude"))),
+                })
+            })
+            .clone()
+    }
+}
+
+/// Reads the patterns of an ignore `file`, relative to `dir`. Like git,
+/// a missing file or invalid patterns are passed over.
+fn load(dir: &Path, file: PathBuf) -> Gitignore {
+    if !file.is_file() {
+        return Gitignore::empty();
+    }
+    let mut builder = GitignoreBuilder::new(dir);
+    let _ = builder.add(file);
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
@@ -0,0 +1,145 @@
+use crate::archive::{Kind, Member};
+use crate::entry::Entry;
+use crate::paths::PathFormat;
+use crate::printf;
+use serde::Serialize;
+use std::fs::{self, FileType, Metadata};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// An entry as printed by `--json`, with the metadata scripts would
+/// otherwise `stat` for
+#[derive(Debug, Serialize)]
+struct JsonEntry {
+    path: String,
+    #[serde(rename = "type")]
+    entry_type: &'static str,
+    size: u64,
+    mode: String,
+    uid: Option<u64>,
+    gid: Option<u64>,
+    atime: Option<i64>,
+    mtime: Option<i64>,
+    ctime: Option<i64>,
+    depth: usize,
+    target: Option<String>,
+}
+
+pub(crate) fn render(entry: &Entry, paths: &PathFormat) -> io::Result<String> {
+    let metadata = entry.metadata()?;
+    let target = if entry.path_is_symlink() {
+        Some(fs::read_link(entry.path())?.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    let json = JsonEntry {
+        path: paths.format(entry.path()),
+        entry_type: type_name(entry.file_type()),
+        size: metadata.len(),
+        mode: format!("{:o}", printf::mode(&metadata) & 0o7777),
+        uid: uid(&metadata),
+        gid: gid(&metadata),
+        atime: metadata.accessed().ok().map(epoch_secs),
+        mtime: metadata.modified().ok().map(epoch_secs),
+        ctime: ctime(&metadata),
+        depth: entry.depth(),
+        target,
+    };
+
+    Ok(serde_json::to_string(&json)?)
+}
+
+/// Renders a member of an archive with the metadata the archive stores
+pub(crate) fn render_member(member: &Member, paths: &PathFormat) -> serde_json::Result<String> {
+    let json = JsonEntry {
+        path: paths.format(&member.path),
+        entry_type: match member.kind {
+            Kind::Dir => "d",
+            Kind::File => "f",
+            Kind::Link => "l",
+            Kind::Other => "?",
+        },
+        size: member.size,
+        mode: format!("{:o}", member.mode & 0o7777),
+        uid: member.uid,
+        gid: member.gid,
+        atime: None,
+        mtime: member.mtime,
+        ctime: None,
+        depth: member.depth,
+        target: member
+            .target
+            .as_ref()
+            .map(|target| target.to_string_lossy().to_string()),
+    };
+
+    serde_json::to_string(&json)
+}
+
+/// The `--type` value matching a file type
+fn type_name(file_type: FileType) -> &'static str {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_socket() {
+            return "s";
+        } else if file_type.is_fifo() {
+            return "p";
+        } else if file_type.is_block_device() {
+            return "b";
+        } else if file_type.is_char_device() {
+            return "c";
+        }
+    }
+
+    if file_type.is_dir() {
+        "d"
+    } else if file_type.is_symlink() {
+        "l"
+    } else if file_type.is_file() {
+        "f"
+    } else {
+        "?"
+    }
+}
+
+fn epoch_secs(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    }
+}
+
+#[cfg(unix)]
+fn uid(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.uid().into())
+}
+
+#[cfg(not(unix))]
+fn uid(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn gid(metadata: &Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.gid().into())
+}
+
+#[cfg(not(unix))]
+fn gid(_metadata: &Metadata) -> Option<u64> {
+    None
+}
+
+#[cfg(unix)]
+fn ctime(metadata: &Metadata) -> Option<i64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ctime())
+}
+
+#[cfg(not(unix))]
+fn ctime(_metadata: &Metadata) -> Option<i64> {
+    None
+}
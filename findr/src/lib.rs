@@ -1,28 +1,38 @@
-use clap::builder::PossibleValue;
-use clap::{Parser, ValueEnum};
-use regex::Regex;
-use walkdir::WalkDir;
-
-#[derive(Debug, Eq, PartialEq, Clone)]
-enum EntryType {
-    Dir,
-    File,
-    Link,
-}
+mod archive;
+mod content;
+mod duplicates;
+mod entry;
+mod error;
+mod filter;
+mod find;
+mod gitignore;
+mod json;
+mod parallel;
+mod paths;
+mod printf;
+mod sort;
+mod summary;
+#[cfg(target_os = "linux")]
+mod watch;
 
-impl ValueEnum for EntryType {
-    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
-        match self {
-            Self::Dir => Some(PossibleValue::new("d")),
-            Self::File => Some(PossibleValue::new("f")),
-            Self::Link => Some(PossibleValue::new("l")),
-        }
-    }
+pub use entry::Follow;
+pub use error::FindError;
+pub use filter::EntryType;
+pub use find::{Find, FindBuilder, Match};
+pub use sort::SortKey;
 
-    fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Dir, Self::File, Self::Link]
-    }
-}
+use archive::Member;
+use clap::Parser;
+use entry::Entry;
+use error::Reporter;
+use filter::Filter;
+use paths::{PathFormat, PathStyle};
+use printf::Format;
+use regex::Regex;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use summary::Summary;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -32,41 +42,394 @@ impl ValueEnum for EntryType {
     name = "findr"
 )]
 pub struct Config {
-    #[arg(help = "Name", value_name = "NAME", long = "name", short = 'n')]
+    #[arg(help = "Name", value_name = "NAME", long = "name", short = 'n', value_parser = parse_name)]
     names: Vec<Regex>,
 
-    #[arg(help = "Entry type", value_name = "TYPE", long = "type", short = 't')]
+    #[arg(help = "Entry type", value_name = "TYPE", long = "type", short = 't', num_args = 1..)]
     entry_types: Vec<EntryType>,
 
+    #[arg(help = "Content", value_name = "REGEX", long = "contains", value_parser = parse_contains)]
+    contains: Option<regex::bytes::Regex>,
+
+    #[arg(help = "MIME type", value_name = "TYPE", long = "mime")]
+    mimes: Vec<String>,
+
+    #[arg(help = "Only empty files and directories", long = "empty")]
+    empty: bool,
+
+    #[arg(help = "Output format", value_name = "FORMAT", long = "printf", value_parser = parse_format)]
+    printf: Option<Format>,
+
+    #[arg(
+        help = "Print sets of files with the same content",
+        long = "duplicates",
+        conflicts_with = "printf"
+    )]
+    duplicates: bool,
+
+    #[arg(
+        help = "Print JSON objects, one per line",
+        long = "json",
+        conflicts_with = "printf"
+    )]
+    json: bool,
+
+    #[arg(
+        help = "Also search the members of .tar, .tar.gz and .zip archives",
+        long = "search-archives",
+        conflicts_with_all = ["printf", "duplicates", "summarize"]
+    )]
+    search_archives: bool,
+
+    #[arg(
+        help = "Print the total size and number of matched files in each directory",
+        long = "summarize",
+        conflicts_with_all = ["printf", "duplicates"]
+    )]
+    summarize: bool,
+
+    #[arg(
+        help = "Count directories below this depth in their ancestor at this depth",
+        value_name = "DEPTH",
+        long = "summarize-depth",
+        requires = "summarize"
+    )]
+    summarize_depth: Option<usize>,
+
+    #[arg(
+        help = "Print sizes like 1.5K, 12M or 3.0G",
+        long = "human",
+        requires = "summarize"
+    )]
+    human: bool,
+
+    #[arg(
+        help = "Walk directories in parallel",
+        value_name = "N",
+        long = "threads"
+    )]
+    threads: Option<NonZeroUsize>,
+
+    #[arg(
+        help = "Sort entries within each directory, or directories by total with --summarize",
+        value_name = "KEY",
+        long = "sort",
+        value_enum
+    )]
+    sort: Option<SortKey>,
+
+    #[arg(help = "Reverse the sort order", long = "reverse", requires = "sort")]
+    reverse: bool,
+
+    #[arg(
+        help = "Never follow symbolic links (default)",
+        short = 'P',
+        overrides_with_all = ["follow_args", "follow_all"]
+    )]
+    follow_none: bool,
+
+    #[arg(
+        help = "Follow symbolic links given as search paths",
+        short = 'H',
+        overrides_with_all = ["follow_none", "follow_all"]
+    )]
+    follow_args: bool,
+
+    #[arg(
+        help = "Follow all symbolic links",
+        short = 'L',
+        overrides_with_all = ["follow_none", "follow_args"]
+    )]
+    follow_all: bool,
+
+    #[arg(
+        help = "Keep reporting entries created, modified or deleted after the search. Writing a new file reports it as created only",
+        long = "watch",
+        conflicts_with_all = ["printf", "json", "duplicates", "summarize"]
+    )]
+    watch: bool,
+
+    #[arg(
+        help = "Skip what .ignore, .gitignore and git's exclude files exclude, and hidden entries",
+        long = "respect-ignore"
+    )]
+    respect_ignore: bool,
+
+    #[arg(
+        help = "Include hidden entries",
+        long = "hidden",
+        overrides_with = "no_hidden"
+    )]
+    hidden: bool,
+
+    #[arg(
+        help = "Skip hidden entries",
+        long = "no-hidden",
+        overrides_with = "hidden"
+    )]
+    no_hidden: bool,
+
+    #[arg(
+        help = "Separator printed between path components",
+        value_name = "STYLE",
+        long = "path-style",
+        value_enum,
+        default_value_t = PathStyle::Native
+    )]
+    path_style: PathStyle,
+
+    #[arg(
+        help = "Print paths relative to this directory",
+        value_name = "DIR",
+        long = "relative-to"
+    )]
+    relative_to: Option<PathBuf>,
+
+    #[arg(
+        help = "Print absolute paths",
+        long = "absolute",
+        conflicts_with = "relative_to"
+    )]
+    absolute: bool,
+
+    #[arg(
+        help = "Don't print errors (the exit status still reports them)",
+        long = "quiet-errors"
+    )]
+    quiet_errors: bool,
+
     #[arg(help = "Search paths", value_name = "PATH", default_value = ".")]
     paths: Vec<String>,
 }
 
 type FindrResult<T> = Result<T, Box<dyn std::error::Error>>;
 
-pub fn run(cfg: Config) -> FindrResult<()> {
-    for path in cfg.paths {
-        for entry in WalkDir::new(path) {
-            match entry {
-                Err(err) => eprint!("{}", err),
-                Ok(entry) => {
-                    if (cfg.entry_types.is_empty()
-                        || cfg.entry_types.iter().any(|et| match et {
-                            EntryType::Dir => entry.file_type().is_dir(),
-                            EntryType::File => entry.file_type().is_file(),
-                            EntryType::Link => entry.file_type().is_symlink(),
-                        }))
-                        && (cfg.names.is_empty()
-                            || cfg
-                                .names
-                                .iter()
-                                .any(|re| re.is_match(&entry.file_name().to_string_lossy())))
-                    {
-                        println!("{}", entry.path().display());
-                    }
+impl Config {
+    fn follow(&self) -> Follow {
+        if self.follow_all {
+            Follow::Always
+        } else if self.follow_args {
+            Follow::Roots
+        } else {
+            Follow::Never
+        }
+    }
+
+    /// The search described by the options, for walking it sequentially
+    fn builder(&self) -> FindBuilder {
+        let (first, rest) = self.paths.split_first().expect("paths has a default");
+        let mut builder = rest
+            .iter()
+            .fold(FindBuilder::new(first), FindBuilder::path)
+            .with_filter(self.filter())
+            .follow(self.follow())
+            .respect_ignore(self.respect_ignore);
+        if self.hidden || self.no_hidden {
+            builder = builder.hidden(self.hidden);
+        }
+        if let Some(key) = self.sort {
+            builder = builder.sort(key).reverse(self.reverse);
+        }
+        builder
+    }
+
+    fn filter(&self) -> Filter {
+        Filter {
+            names: self.names.clone(),
+            entry_types: self.entry_types.clone(),
+            contains: self.contains.clone(),
+            mimes: self.mimes.clone(),
+            empty: self.empty,
+        }
+    }
+}
+
+fn parse_name(name: &str) -> Result<Regex, String> {
+    Regex::new(name).map_err(|_| format!("Invalid --name \"{}\"", name))
+}
+
+fn parse_contains(contains: &str) -> Result<regex::bytes::Regex, String> {
+    regex::bytes::Regex::new(contains).map_err(|_| format!("Invalid --contains \"{}\"", contains))
+}
+
+fn parse_format(format: &str) -> Result<Format, String> {
+    Format::parse(format).map_err(|err| format!("Invalid --printf \"{}\": {}", format, err))
+}
+
+/// Runs the search, failing with exit status 1 if any path couldn't be read
+pub fn run(cfg: Config) -> FindrResult<ExitCode> {
+    let reporter = Reporter::new(cfg.quiet_errors);
+    let paths = PathFormat::new(cfg.path_style, cfg.absolute, cfg.relative_to.as_deref())?;
+
+    if cfg.duplicates {
+        let mut files = vec![];
+        walk(&cfg, &reporter, |found| {
+            // Archives aren't searched for duplicates
+            if let Found::Entry(entry) = found {
+                files.push(entry);
+            }
+        })?;
+        duplicates::print(&duplicates::find(files, &reporter), cfg.json, &paths)?;
+    } else if cfg.summarize {
+        let mut summary = Summary::new(cfg.summarize_depth);
+        walk(&cfg, &reporter, |found| {
+            // Archives aren't searched for summaries
+            if let Found::Entry(entry) = found {
+                summary.add(&entry, &reporter);
+            }
+        })?;
+        let by_size = cfg.sort == Some(SortKey::Size);
+        let totals = summary.totals(by_size, cfg.reverse);
+        summary::print(&totals, cfg.human, cfg.json, &paths)?;
+    } else {
+        walk(&cfg, &reporter, |found| match found {
+            Found::Entry(entry) => print_entry(&cfg, &reporter, &paths, &entry),
+            Found::Member(member) => print_member(&cfg, &reporter, &paths, &member),
+        })?;
+    }
+
+    if cfg.watch {
+        watch(&cfg, &reporter, &paths)?;
+    }
+
+    Ok(if reporter.failed() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// What a walk hands on: an entry that passed the filters, or such a member
+/// of an archive found along the way
+enum Found {
+    Entry(Entry),
+    Member(Member),
+}
+
+/// Walks the search paths, handing everything that passes the filters to
+/// `on_found` and reporting errors as they are found
+fn walk(cfg: &Config, reporter: &Reporter, mut on_found: impl FnMut(Found)) -> FindrResult<()> {
+    let builder = cfg.builder();
+    let follow = cfg.follow();
+    let filter = cfg.filter();
+    let matches = |entry: &Entry| {
+        filter.is_match(entry).unwrap_or_else(|err| {
+            reporter.report(err);
+            false
+        })
+    };
+    let archive = |entry: &Entry| {
+        cfg.search_archives
+            .then(|| archive::Format::of(entry))
+            .flatten()
+    };
+
+    // Archives are searched whether or not they match themselves, so the
+    // walkers keep them and they are checked here. `checked` tells whether
+    // the walker already checked the other entries.
+    let mut on_entry = |entry: Entry, checked: bool| {
+        let format = archive(&entry);
+        let (path, depth) = (entry.path().to_path_buf(), entry.depth());
+        if (checked && format.is_none()) || matches(&entry) {
+            on_found(Found::Entry(entry));
+        }
+
+        if let Some(format) = format {
+            let searched = archive::search(&path, depth, format, |member, content| {
+                match filter.is_member_match(&member, content) {
+                    Ok(true) => on_found(Found::Member(member)),
+                    Ok(false) => {}
+                    Err(err) => reporter.report(FindError::io(&member.path, err)),
+                }
+            });
+            if let Err(err) = searched {
+                reporter.report(FindError::io(&path, err));
+            }
+        }
+    };
+
+    match cfg.threads {
+        None => {
+            let builder = if cfg.search_archives {
+                builder.with_filter(Filter::default())
+            } else {
+                builder
+            };
+            for found in builder.build() {
+                match found {
+                    Err(err) => reporter.report(err),
+                    Ok(found) => on_entry(found.into_entry(), !cfg.search_archives),
+                }
+            }
+        }
+        Some(threads) => {
+            let mut found = vec![vec![]; cfg.paths.len()];
+            parallel::walk(
+                &cfg.paths,
+                threads.get(),
+                follow,
+                &builder.skip(),
+                |entry| archive(entry).is_some() || matches(entry),
+                |root, item| match item {
+                    Err(err) => reporter.report(err),
+                    Ok(entry) if cfg.sort.is_some() => found[root].push(entry),
+                    Ok(entry) => on_entry(entry, true),
+                },
+            )?;
+
+            if let Some(key) = cfg.sort {
+                for mut entries in found {
+                    entries.sort_by(|a, b| {
+                        key.tree_compare(a.path(), b.path(), follow == Follow::Always, cfg.reverse)
+                    });
+                    entries.into_iter().for_each(|entry| on_entry(entry, true));
                 }
             }
         }
     }
     Ok(())
 }
+
+#[cfg(target_os = "linux")]
+fn watch(cfg: &Config, reporter: &Reporter, paths: &PathFormat) -> FindrResult<()> {
+    let skip = cfg.builder().skip();
+    Ok(watch::watch(
+        &cfg.paths,
+        cfg.follow(),
+        &cfg.filter(),
+        &skip,
+        paths,
+        reporter,
+    )?)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn watch(_cfg: &Config, _reporter: &Reporter, _paths: &PathFormat) -> FindrResult<()> {
+    Err("--watch is only supported on Linux".into())
+}
+
+fn print_member(cfg: &Config, reporter: &Reporter, paths: &PathFormat, member: &Member) {
+    let output = if cfg.json {
+        json::render_member(member, paths)
+    } else {
+        Ok(paths.format(&member.path))
+    };
+
+    match output {
+        Ok(output) => println!("{}", output),
+        Err(err) => reporter.report(FindError::io(&member.path, err.into())),
+    }
+}
+
+fn print_entry(cfg: &Config, reporter: &Reporter, paths: &PathFormat, entry: &Entry) {
+    let output = match &cfg.printf {
+        None if cfg.json => json::render(entry, paths).map(|json| json + "\n"),
+        None => Ok(format!("{}\n", paths.format(entry.path()))),
+        Some(format) => format.render(entry, paths),
+    };
+
+    match output {
+        Ok(output) => print!("{}", output),
+        Err(err) => reporter.report(FindError::io(entry.path(), err)),
+    }
+}
@@ -1,7 +1,8 @@
 use clap::Parser;
 use findr::{run, Config};
+use std::process::ExitCode;
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
     let cfg = Config::parse();
 
     run(cfg)
@@ -0,0 +1,178 @@
+use crate::entry::{Entry, Follow};
+use crate::error::FindError;
+use crate::gitignore::Skip;
+use rayon::{Scope, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread;
+
+type Item = Result<Entry, FindError>;
+
+/// Walks `paths` using a pool of `threads` workers, each directory being a
+/// task that can be stolen by an idle worker. Entries left out by `skip` are
+/// not descended into. Entries accepted by `filter`
+/// and any errors are handed to `sink` on the calling thread, along with the
+/// index of the path they were found under, in no particular order.
+pub(crate) fn walk<F, S>(
+    paths: &[String],
+    threads: usize,
+    follow: Follow,
+    skip: &Skip,
+    filter: F,
+    mut sink: S,
+) -> Result<(), ThreadPoolBuildError>
+where
+    F: Fn(&Entry) -> bool + Sync,
+    S: FnMut(usize, Item),
+{
+    let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let (tx, rx) = mpsc::channel();
+    let walker = &Walker {
+        follow,
+        skip,
+        filter,
+    };
+
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            pool.scope(|s| {
+                for (root, path) in paths.iter().enumerate() {
+                    let tx = Sink {
+                        root,
+                        tx: tx.clone(),
+                    };
+                    s.spawn(move |s| walker.visit_root(s, Path::new(path), tx));
+                }
+            })
+        });
+
+        for (root, item) in rx {
+            sink(root, item);
+        }
+    });
+
+    Ok(())
+}
+
+struct Walker<'a, F> {
+    follow: Follow,
+    skip: &'a Skip,
+    filter: F,
+}
+
+/// A directory being walked when following symlinks, used to detect loops
+struct Ancestor {
+    path: PathBuf,
+    canonical: PathBuf,
+    parent: Option<Arc<Ancestor>>,
+}
+
+impl<F> Walker<'_, F>
+where
+    F: Fn(&Entry) -> bool + Sync,
+{
+    fn visit_root<'s>(&'s self, scope: &Scope<'s>, path: &Path, tx: Sink) {
+        match Entry::from_path(path.to_path_buf(), 0, self.follow != Follow::Never) {
+            Err(err) => send(&tx, Err(FindError::io(path, err))),
+            Ok(entry) => self.visit(scope, entry, None, tx),
+        }
+    }
+
+    fn visit<'s>(
+        &'s self,
+        scope: &Scope<'s>,
+        entry: Entry,
+        parent: Option<Arc<Ancestor>>,
+        tx: Sink,
+    ) {
+        if self.skip.skips(&entry) {
+            return;
+        }
+
+        let is_dir = entry.file_type().is_dir();
+        let depth = entry.depth();
+        let path = entry.path().to_path_buf();
+
+        let ancestors = match self.follow {
+            Follow::Always if is_dir => match self.check_loop(&entry, parent) {
+                Ok(ancestors) => ancestors,
+                Err(err) => return send(&tx, Err(err)),
+            },
+            _ => None,
+        };
+
+        if (self.filter)(&entry) {
+            send(&tx, Ok(entry));
+        }
+
+        if !is_dir {
+            return;
+        }
+
+        let children = match fs::read_dir(&path) {
+            Err(err) => return send(&tx, Err(FindError::io(&path, err))),
+            Ok(children) => children,
+        };
+
+        for child in children {
+            let child = child.and_then(|child| match child.file_type()? {
+                file_type if file_type.is_symlink() && self.follow == Follow::Always => {
+                    Entry::from_path(child.path(), depth + 1, true)
+                }
+                file_type => Ok(Entry::new(child.path(), file_type, depth + 1)),
+            });
+
+            match child {
+                Err(err) => send(&tx, Err(FindError::io(&path, err))),
+                Ok(child) if child.file_type().is_dir() => {
+                    let tx = tx.clone();
+                    let ancestors = ancestors.clone();
+                    scope.spawn(move |s| self.visit(s, child, ancestors, tx));
+                }
+                Ok(child) => self.visit(scope, child, None, tx.clone()),
+            }
+        }
+    }
+
+    /// Fails if `entry` is a followed symlink to one of its ancestors,
+    /// otherwise returns the ancestors for its children
+    fn check_loop(
+        &self,
+        entry: &Entry,
+        parent: Option<Arc<Ancestor>>,
+    ) -> Result<Option<Arc<Ancestor>>, FindError> {
+        let path = entry.path();
+        let canonical = fs::canonicalize(path).map_err(|err| FindError::io(path, err))?;
+
+        let mut ancestor = parent.as_deref().filter(|_| entry.path_is_symlink());
+        while let Some(dir) = ancestor {
+            if dir.canonical == canonical {
+                return Err(FindError::Loop {
+                    path: path.to_path_buf(),
+                    ancestor: dir.path.clone(),
+                });
+            }
+            ancestor = dir.parent.as_deref();
+        }
+
+        Ok(Some(Arc::new(Ancestor {
+            path: path.to_path_buf(),
+            canonical,
+            parent,
+        })))
+    }
+}
+
+/// The sending half of the results channel for one of the walked paths
+#[derive(Clone)]
+struct Sink {
+    root: usize,
+    tx: Sender<(usize, Item)>,
+}
+
+fn send(sink: &Sink, item: Item) {
+    // The receiver only goes away once the walk is over
+    let _ = sink.tx.send((sink.root, item));
+}
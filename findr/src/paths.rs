@@ -0,0 +1,111 @@
+use clap::ValueEnum;
+use std::borrow::Cow;
+use std::env;
+use std::io;
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
+
+/// The separator printed between path components
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub(crate) enum PathStyle {
+    /// The host's separator
+    #[default]
+    Native,
+    /// Always `/`
+    Posix,
+}
+
+/// What printed paths start from
+#[derive(Debug)]
+enum Base {
+    /// The search paths as given
+    Given,
+    Absolute,
+    RelativeTo(PathBuf),
+}
+
+/// Formats the paths that are printed
+#[derive(Debug)]
+pub(crate) struct PathFormat {
+    style: PathStyle,
+    cwd: PathBuf,
+    base: Base,
+}
+
+impl PathFormat {
+    /// Paths are printed absolute with `absolute`, relative to `relative_to`
+    /// when it is set, or else as found from the search paths
+    pub(crate) fn new(
+        style: PathStyle,
+        absolute: bool,
+        relative_to: Option<&Path>,
+    ) -> io::Result<Self> {
+        let cwd = env::current_dir()?;
+        let base = match relative_to {
+            Some(dir) => Base::RelativeTo(normalize(&cwd.join(dir))),
+            None if absolute => Base::Absolute,
+            None => Base::Given,
+        };
+        Ok(PathFormat { style, cwd, base })
+    }
+
+    pub(crate) fn format(&self, path: &Path) -> String {
+        let path: Cow<'_, Path> = match &self.base {
+            Base::Given => path.into(),
+            Base::Absolute => normalize(&self.cwd.join(path)).into(),
+            Base::RelativeTo(dir) => relative(&normalize(&self.cwd.join(path)), dir).into(),
+        };
+
+        let text = path.to_string_lossy();
+        match self.style {
+            PathStyle::Posix if MAIN_SEPARATOR != '/' => text.replace(MAIN_SEPARATOR, "/"),
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// Removes `.` components and resolves `..` ones without looking at the
+/// filesystem, so symlinks in the path are kept
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(normal.components().next_back(), Some(Component::Normal(_))) =>
+            {
+                normal.pop();
+            }
+            Component::ParentDir if normal.has_root() => {}
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+/// The way from `dir` to `path`, both normalized absolute paths. Paths on
+/// another drive stay absolute.
+fn relative(path: &Path, dir: &Path) -> PathBuf {
+    if path.components().next() != dir.components().next() {
+        return path.to_path_buf();
+    }
+
+    let mut path_components = path.components().peekable();
+    let mut dir_components = dir.components().peekable();
+    while let (Some(a), Some(b)) = (path_components.peek(), dir_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        dir_components.next();
+    }
+
+    let relative: PathBuf = dir_components
+        .map(|_| Component::ParentDir)
+        .chain(path_components)
+        .collect();
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
+}
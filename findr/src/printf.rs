@@ -0,0 +1,231 @@
+use crate::entry::Entry;
+use crate::paths::PathFormat;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local};
+use std::fmt::Write;
+use std::fs::Metadata;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Directive {
+    Literal(String),
+    Path,
+    Name,
+    Parent,
+    Size,
+    Mode,
+    SymbolicMode,
+    Owner,
+    Group,
+    Mtime(String),
+    Depth,
+    LinkTarget,
+}
+
+/// Output format for `--printf`, following GNU find directives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Format(Vec<Directive>);
+
+impl Format {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        let mut directives = vec![];
+        let mut literal = String::new();
+        let mut chars = format.chars();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' => match chars.next() {
+                    Some('n') => literal.push('\n'),
+                    Some('t') => literal.push('\t'),
+                    Some('r') => literal.push('\r'),
+                    Some('0') => literal.push('\0'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                    None => literal.push('\\'),
+                },
+                '%' => {
+                    let directive = match chars.next() {
+                        Some('%') => {
+                            literal.push('%');
+                            continue;
+                        }
+                        Some('p') => Directive::Path,
+                        Some('f') => Directive::Name,
+                        Some('h') => Directive::Parent,
+                        Some('s') => Directive::Size,
+                        Some('m') => Directive::Mode,
+                        Some('M') => Directive::SymbolicMode,
+                        Some('u') => Directive::Owner,
+                        Some('g') => Directive::Group,
+                        Some('t') => Directive::Mtime("%a %b %e %H:%M:%S %Y".to_string()),
+                        Some('T') => match chars.next() {
+                            Some('@') => Directive::Mtime("%s".to_string()),
+                            // GNU prints the nanoseconds with a tenth digit
+                            Some('+') => Directive::Mtime("%Y-%m-%d+%H:%M:%S.%f0".to_string()),
+                            Some(spec)
+                                if spec.is_ascii_alphabetic()
+                                    && StrftimeItems::new(&format!("%{spec}"))
+                                        .all(|item| item != Item::Error) =>
+                            {
+                                Directive::Mtime(format!("%{spec}"))
+                            }
+                            Some(spec) => return Err(format!("unknown time directive %T{spec}")),
+                            None => return Err("missing time directive after %T".to_string()),
+                        },
+                        Some('d') => Directive::Depth,
+                        Some('l') => Directive::LinkTarget,
+                        Some(other) => return Err(format!("unknown directive %{other}")),
+                        None => return Err("trailing % in format".to_string()),
+                    };
+
+                    if !literal.is_empty() {
+                        directives.push(Directive::Literal(std::mem::take(&mut literal)));
+                    }
+                    directives.push(directive);
+                }
+                _ => literal.push(ch),
+            }
+        }
+
+        if !literal.is_empty() {
+            directives.push(Directive::Literal(literal));
+        }
+
+        Ok(Format(directives))
+    }
+
+    pub(crate) fn render(&self, entry: &Entry, paths: &PathFormat) -> std::io::Result<String> {
+        let mut metadata: Option<Metadata> = None;
+        let mut output = String::new();
+
+        for directive in &self.0 {
+            match directive {
+                Directive::Literal(text) => output.push_str(text),
+                Directive::Path => output.push_str(&paths.format(entry.path())),
+                Directive::Name => output.push_str(&entry.file_name().to_string_lossy()),
+                Directive::Parent => output.push_str(&paths.format(parent(entry.path()))),
+                Directive::Depth => write!(output, "{}", entry.depth()).unwrap(),
+                Directive::LinkTarget => {
+                    if entry.path_is_symlink() {
+                        let target = std::fs::read_link(entry.path())?;
+                        output.push_str(&target.to_string_lossy());
+                    }
+                }
+                _ => {
+                    if metadata.is_none() {
+                        metadata = Some(entry.metadata()?);
+                    }
+                    let metadata = metadata.as_ref().unwrap();
+
+                    match directive {
+                        Directive::Size => write!(output, "{}", metadata.len()).unwrap(),
+                        Directive::Mode => write!(output, "{:o}", mode(metadata) & 0o7777).unwrap(),
+                        Directive::SymbolicMode => output.push_str(&symbolic_mode(metadata)),
+                        Directive::Owner => output.push_str(&owner(metadata)),
+                        Directive::Group => output.push_str(&group(metadata)),
+                        Directive::Mtime(spec) => {
+                            let mtime: DateTime<Local> = metadata.modified()?.into();
+                            write!(output, "{}", mtime.format(spec)).unwrap();
+                        }
+                        _ => unreachable!("directive does not need metadata"),
+                    }
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+fn parent(path: &Path) -> &Path {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn mode(metadata: &Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode()
+}
+
+#[cfg(not(unix))]
+pub(crate) fn mode(metadata: &Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
+
+fn symbolic_mode(metadata: &Metadata) -> String {
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else {
+        special_kind(&file_type).unwrap_or('-')
+    };
+
+    let mode = mode(metadata);
+    let perms =
+        "rwxrwxrwx"
+            .chars()
+            .enumerate()
+            .map(|(i, ch)| if mode & (0o400 >> i) != 0 { ch } else { '-' });
+
+    std::iter::once(kind).chain(perms).collect()
+}
+
+/// The `ls` letter of a socket, FIFO or device
+#[cfg(unix)]
+fn special_kind(file_type: &std::fs::FileType) -> Option<char> {
+    use std::os::unix::fs::FileTypeExt;
+    if file_type.is_socket() {
+        Some('s')
+    } else if file_type.is_fifo() {
+        Some('p')
+    } else if file_type.is_block_device() {
+        Some('b')
+    } else if file_type.is_char_device() {
+        Some('c')
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_kind(_file_type: &std::fs::FileType) -> Option<char> {
+    None
+}
+
+#[cfg(unix)]
+fn owner(metadata: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    uzers::get_user_by_uid(metadata.uid())
+        .map(|user| user.name().to_string_lossy().to_string())
+        .unwrap_or_else(|| metadata.uid().to_string())
+}
+
+#[cfg(not(unix))]
+fn owner(_metadata: &Metadata) -> String {
+    String::new()
+}
+
+#[cfg(unix)]
+fn group(metadata: &Metadata) -> String {
+    use std::os::unix::fs::MetadataExt;
+    uzers::get_group_by_gid(metadata.gid())
+        .map(|group| group.name().to_string_lossy().to_string())
+        .unwrap_or_else(|| metadata.gid().to_string())
+}
+
+#[cfg(not(unix))]
+fn group(_metadata: &Metadata) -> String {
+    String::new()
+}
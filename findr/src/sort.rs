@@ -0,0 +1,67 @@
+use clap::ValueEnum;
+use std::cmp::Ordering;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What the entries of each directory are ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    Name,
+    Size,
+    Mtime,
+    Path,
+}
+
+impl SortKey {
+    /// Compares two entries from the same directory, falling back to the
+    /// file name so ties are ordered the same way on every machine
+    pub(crate) fn compare(&self, a: &Path, b: &Path, follow: bool) -> Ordering {
+        let by_name = || a.file_name().cmp(&b.file_name());
+        let metadata = |path: &Path| {
+            if follow {
+                fs::metadata(path)
+            } else {
+                fs::symlink_metadata(path)
+            }
+        };
+
+        match self {
+            Self::Name => by_name(),
+            Self::Path => a.cmp(b),
+            Self::Size => {
+                let size = |path: &Path| metadata(path).map(|m| m.len()).ok();
+                size(a).cmp(&size(b)).then_with(by_name)
+            }
+            Self::Mtime => {
+                let mtime = |path: &Path| metadata(path).and_then(|m| m.modified()).ok();
+                mtime(a).cmp(&mtime(b)).then_with(by_name)
+            }
+        }
+    }
+
+    /// Orders two paths below the same root the way a sorted depth-first walk
+    /// visits them: a directory comes before its contents and siblings are
+    /// ordered by `compare`
+    pub(crate) fn tree_compare(&self, a: &Path, b: &Path, follow: bool, reverse: bool) -> Ordering {
+        let mut parent = PathBuf::new();
+        let mut a_components = a.components();
+        let mut b_components = b.components();
+
+        loop {
+            match (a_components.next(), b_components.next()) {
+                (None, None) => return Ordering::Equal,
+                (None, Some(_)) => return Ordering::Less,
+                (Some(_), None) => return Ordering::Greater,
+                (Some(a), Some(b)) if a == b => parent.push(a),
+                (Some(a), Some(b)) => {
+                    let ordering = self.compare(&parent.join(a), &parent.join(b), follow);
+                    return if reverse {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    };
+                }
+            }
+        }
+    }
+}
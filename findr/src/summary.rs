@@ -0,0 +1,122 @@
+use crate::entry::Entry;
+use crate::error::{FindError, Reporter};
+use crate::paths::PathFormat;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// The size and number of the matched files below a directory
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct Total {
+    path: PathBuf,
+    size: u64,
+    files: u64,
+}
+
+/// Adds up the matched files into the directories they are in, like `du`
+#[derive(Debug, Default)]
+pub(crate) struct Summary {
+    max_depth: Option<usize>,
+    totals: BTreeMap<PathBuf, Total>,
+}
+
+impl Summary {
+    /// Directories deeper than `max_depth` are counted in their ancestor at
+    /// that depth
+    pub(crate) fn new(max_depth: Option<usize>) -> Self {
+        Summary {
+            max_depth,
+            totals: BTreeMap::new(),
+        }
+    }
+
+    /// Counts `entry` in every directory above it, up to its search path.
+    /// Directories themselves only hold what is counted below them.
+    pub(crate) fn add(&mut self, entry: &Entry, reporter: &Reporter) {
+        if entry.file_type().is_dir() || entry.depth() == 0 {
+            return;
+        }
+        let size = match entry.metadata() {
+            Err(err) => return reporter.report(FindError::io(entry.path(), err)),
+            Ok(metadata) => metadata.len(),
+        };
+
+        let deepest = entry.depth() - 1;
+        let skipped = self.max_depth.map_or(0, |max| deepest.saturating_sub(max));
+        for dir in entry
+            .path()
+            .ancestors()
+            .skip(1 + skipped)
+            .take(deepest + 1 - skipped)
+        {
+            let total = self
+                .totals
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| Total {
+                    path: dir.to_path_buf(),
+                    ..Total::default()
+                });
+            total.size += size;
+            total.files += 1;
+        }
+    }
+
+    /// The directories ordered by path, or by size when `by_size` is set
+    pub(crate) fn totals(self, by_size: bool, reverse: bool) -> Vec<Total> {
+        let mut totals: Vec<_> = self.totals.into_values().collect();
+        if by_size {
+            totals.sort_by(|a, b| a.size.cmp(&b.size).then_with(|| a.path.cmp(&b.path)));
+        }
+        if reverse {
+            totals.reverse();
+        }
+        totals
+    }
+}
+
+pub(crate) fn print(
+    totals: &[Total],
+    human: bool,
+    json: bool,
+    paths: &PathFormat,
+) -> serde_json::Result<()> {
+    for total in totals {
+        let path = paths.format(&total.path);
+        if json {
+            let total = Total {
+                path: PathBuf::from(path),
+                ..*total
+            };
+            println!("{}", serde_json::to_string(&total)?);
+        } else {
+            let size = if human {
+                human_size(total.size)
+            } else {
+                total.size.to_string()
+            };
+            println!("{}\t{}\t{}", size, total.files, path);
+        }
+    }
+    Ok(())
+}
+
+/// Formats `size` with binary units like `du -h`: `512`, `1.5K`, `12M`
+fn human_size(size: u64) -> String {
+    const UNITS: [&str; 6] = ["K", "M", "G", "T", "P", "E"];
+
+    let mut value = size as f64;
+    let mut unit = None;
+    for next in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = Some(next);
+    }
+
+    match unit {
+        None => size.to_string(),
+        Some(unit) if value < 10.0 => format!("{:.1}{}", value, unit),
+        Some(unit) => format!("{:.0}{}", value, unit),
+    }
+}
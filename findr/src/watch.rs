@@ -0,0 +1,199 @@
+use crate::entry::{Entry, Follow};
+use crate::error::{FindError, Reporter};
+use crate::filter::Filter;
+use crate::gitignore::Skip;
+use crate::paths::PathFormat;
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// How much of the inotify queue is read at once
+const BUFFER_LEN: usize = 64 * 1024;
+
+/// What happened to a watched entry
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    Created,
+    Modified,
+    Deleted,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Created => "created",
+            Self::Modified => "modified",
+            Self::Deleted => "deleted",
+        })
+    }
+}
+
+/// Watches every directory below `paths` and prints a line tagged with the
+/// event for each entry created, modified or deleted that passes `filter`.
+/// Writing a new file only reports it as created, not also as modified.
+/// Returns once there is nothing left to watch.
+pub(crate) fn watch(
+    paths: &[String],
+    follow: Follow,
+    filter: &Filter,
+    skip: &Skip,
+    path_format: &PathFormat,
+    reporter: &Reporter,
+) -> io::Result<()> {
+    let mut watcher = Watcher {
+        inotify: Inotify::init()?,
+        dirs: HashMap::new(),
+        created: HashSet::new(),
+        follow,
+        filter,
+        skip,
+        paths: path_format,
+        reporter,
+    };
+    for path in paths {
+        watcher.add_tree(Path::new(path), 0, false);
+    }
+
+    let mut buffer = vec![0; BUFFER_LEN];
+    while !watcher.dirs.is_empty() {
+        let events: Vec<_> = watcher
+            .inotify
+            .read_events_blocking(&mut buffer)?
+            .map(|event| event.to_owned())
+            .collect();
+
+        for event in events {
+            if event.mask.contains(EventMask::Q_OVERFLOW) {
+                let err = io::Error::other("too many events, some were lost");
+                reporter.report(FindError::io(Path::new(""), err));
+                continue;
+            }
+            if event.mask.contains(EventMask::IGNORED) {
+                watcher.dirs.remove(&event.wd);
+                continue;
+            }
+
+            let (Some((dir, depth)), Some(name)) = (watcher.dirs.get(&event.wd), event.name) else {
+                continue;
+            };
+            let path = dir.join(&name);
+            let depth = depth + 1;
+            let is_dir = event.mask.contains(EventMask::ISDIR);
+
+            if event
+                .mask
+                .intersects(EventMask::CREATE | EventMask::MOVED_TO)
+            {
+                if is_dir {
+                    watcher.add_tree(&path, depth, true);
+                } else {
+                    if event.mask.contains(EventMask::CREATE) {
+                        watcher.created.insert(path.clone());
+                    }
+                    watcher.report(Event::Created, &path, depth);
+                }
+            } else if event.mask.contains(EventMask::CLOSE_WRITE) {
+                // The first write of a new file is part of creating it
+                if !watcher.created.remove(&path) {
+                    watcher.report(Event::Modified, &path, depth);
+                }
+            } else if event
+                .mask
+                .intersects(EventMask::DELETE | EventMask::MOVED_FROM)
+            {
+                watcher.created.remove(&path);
+                if !skip.skips_path(&path, depth, is_dir)
+                    && filter.is_deleted_match(name.as_os_str(), is_dir)
+                {
+                    println!("{}\t{}", Event::Deleted, watcher.paths.format(&path));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+struct Watcher<'a> {
+    inotify: Inotify,
+    /// The watched directories and their depth below the search path
+    dirs: HashMap<WatchDescriptor, (PathBuf, usize)>,
+    /// New files whose first write hasn't been closed yet
+    created: HashSet<PathBuf>,
+    follow: Follow,
+    filter: &'a Filter,
+    skip: &'a Skip,
+    paths: &'a PathFormat,
+    reporter: &'a Reporter,
+}
+
+impl Watcher<'_> {
+    /// Watches the directories below `path`, which is `depth` levels below
+    /// its search path. When the tree was just created, its entries are
+    /// reported as created since their own events came before the watches.
+    fn add_tree(&mut self, path: &Path, depth: usize, created: bool) {
+        let mut walker = WalkDir::new(path)
+            .follow_links(self.follow == Follow::Always)
+            .follow_root_links(self.follow != Follow::Never)
+            .into_iter();
+
+        while let Some(entry) = walker.next() {
+            let entry = match entry {
+                Err(err) => {
+                    self.reporter.report(FindError::from(err));
+                    continue;
+                }
+                Ok(entry) => entry,
+            };
+            let depth = depth + entry.depth();
+            if self
+                .skip
+                .skips_path(entry.path(), depth, entry.file_type().is_dir())
+            {
+                if entry.file_type().is_dir() {
+                    walker.skip_current_dir();
+                }
+                continue;
+            }
+
+            if created {
+                self.report(Event::Created, entry.path(), depth);
+            }
+            if entry.file_type().is_dir() {
+                let mask = WatchMask::CREATE
+                    | WatchMask::CLOSE_WRITE
+                    | WatchMask::DELETE
+                    | WatchMask::MOVED_FROM
+                    | WatchMask::MOVED_TO;
+                match self.inotify.watches().add(entry.path(), mask) {
+                    Err(err) => self.reporter.report(FindError::io(entry.path(), err)),
+                    Ok(wd) => {
+                        self.dirs.insert(wd, (entry.into_path(), depth));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Prints `event` for the entry at `path` if it passes the filters
+    fn report(&self, event: Event, path: &Path, depth: usize) {
+        let follow = self.follow == Follow::Always;
+        let entry = match Entry::from_path(path.to_path_buf(), depth, follow) {
+            // Already gone again, its deletion is reported next
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return,
+            Err(err) => return self.reporter.report(FindError::io(path, err)),
+            Ok(entry) => entry,
+        };
+
+        if self.skip.skips(&entry) {
+            return;
+        }
+        match self.filter.is_match(&entry) {
+            Err(err) => self.reporter.report(err),
+            Ok(true) => println!("{}\t{}", event, self.paths.format(entry.path())),
+            Ok(false) => {}
+        }
+    }
+}
@@ -30,7 +30,7 @@ fn skips_bad_dir() -> TestResult {
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -39,7 +39,7 @@ fn skips_bad_dir() -> TestResult {
 #[test]
 fn dies_bad_name() -> TestResult {
     Command::cargo_bin(PRG)?
-        .args(&["--name", "*.csv"])
+        .args(["--name", "*.csv"])
         .assert()
         .failure()
         .stderr(predicate::str::contains("Invalid --name \"*.csv\""));
@@ -49,25 +49,77 @@ fn dies_bad_name() -> TestResult {
 // --------------------------------------------------
 #[test]
 fn dies_bad_type() -> TestResult {
-    let expected = "error: 'x' isn't a valid value for '--type <TYPE>...'";
+    let expected = "error: 'z' isn't a valid value for '--type <TYPE>...'";
     Command::cargo_bin(PRG)?
-        .args(&["--type", "x"])
+        .args(["--type", "z"])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_bad_printf() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--printf", "%z"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Invalid --printf \"%z\": unknown directive %z",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_printf_time() -> TestResult {
+    for spec in ["%TQ", "%TE"] {
+        Command::cargo_bin(PRG)?
+            .args(["--printf", spec])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(format!(
+                "Invalid --printf \"{spec}\": unknown time directive {spec}"
+            )));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_time_plus() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a/b/b.csv", "--printf", "%T+\n"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            r"^\d{4}-\d{2}-\d{2}\+\d{2}:\d{2}:\d{2}\.\d{10}\n$",
+        )?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_contains() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--contains", "("])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid --contains \"(\""));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[cfg(windows)]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Owned(format!("{}.windows", expected_file))
     format!("{}.windows", expected_file).into()
 }
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -90,6 +142,21 @@ fn run(args: &[&str], expected_file: &str) -> TestResult {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_ordered(args: &[&str], expected_file: &str) -> TestResult {
+    let file = format_file_name(expected_file);
+    let expected = fs::read_to_string(file.as_ref())?;
+
+    let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        expected.lines().collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn path1() -> TestResult {
@@ -293,14 +360,18 @@ fn unreadable_dir() -> TestResult {
     //permissions.set_mode(0o000);
 
     std::process::Command::new("chmod")
-        .args(&["000", dirname])
+        .args(["000", dirname])
         .status()
         .expect("failed");
 
-    let cmd = Command::cargo_bin(PRG)?
-        .arg("tests/inputs")
-        .assert()
-        .success();
+    // Permissions aren't enforced for root, who reads the directory fine
+    let is_root = uzers::get_effective_uid() == 0;
+    let cmd = Command::cargo_bin(PRG)?.arg("tests/inputs").assert();
+    let cmd = if is_root {
+        cmd.success()
+    } else {
+        cmd.failure()
+    };
     fs::remove_dir(dirname)?;
 
     let out = cmd.get_output();
@@ -308,8 +379,968 @@ fn unreadable_dir() -> TestResult {
     let lines: Vec<&str> = stdout.split("\n").filter(|s| !s.is_empty()).collect();
 
     assert_eq!(lines.len(), 17);
+    assert!(lines.contains(&dirname));
+    if is_root {
+        return Ok(());
+    }
 
     let stderr = String::from_utf8(out.stderr.clone())?;
     assert!(stderr.contains("cant-touch-this: Permission denied"));
     Ok(())
 }
+
+// --------------------------------------------------
+#[test]
+fn printf_depth_size_name() -> TestResult {
+    run(
+        &["tests/inputs", "-t", "f", "--printf", "%d %s %f\n"],
+        "tests/expected/printf_depth_size_name.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_parent_name_link() -> TestResult {
+    run(
+        &["tests/inputs", "-n", "csv", "--printf", "[%l] %h %f\n"],
+        "tests/expected/printf_parent_name_link.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_path1() -> TestResult {
+    run(
+        &["tests/inputs", "--threads", "4"],
+        "tests/expected/path1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_type_f_name_a() -> TestResult {
+    run(
+        &["tests/inputs", "--threads", "2", "-t", "f", "-n", "a"],
+        "tests/expected/type_f_name_a.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn threads_skips_bad_dir() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("{}: .* [(]os error [23][)]", &bad);
+    Command::cargo_bin(PRG)?
+        .args([&bad, "--threads", "2"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn error_kind_per_line() -> TestResult {
+    let bad = gen_bad_file();
+    let expected = format!("^findr: not-found: {}: .*\n$", &bad);
+    Command::cargo_bin(PRG)?
+        .args([&bad, "tests/inputs/g.csv"])
+        .assert()
+        .failure()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn quiet_errors() -> TestResult {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args([&bad, "tests/inputs/g.csv", "--quiet-errors"])
+        .assert()
+        .failure()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn clean_run_succeeds() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .arg("tests/inputs/g.csv")
+        .assert()
+        .success()
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_name() -> TestResult {
+    run_ordered(
+        &["tests/inputs", "--sort", "name"],
+        "tests/expected/sort_name.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_name_reverse() -> TestResult {
+    run_ordered(
+        &["tests/inputs", "--sort", "name", "--reverse"],
+        "tests/expected/sort_name_reverse.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_path_threads() -> TestResult {
+    run_ordered(
+        &["tests/inputs", "--sort", "path", "--threads", "4"],
+        "tests/expected/sort_name.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_name_reverse_threads() -> TestResult {
+    run_ordered(
+        &[
+            "tests/inputs",
+            "--sort",
+            "name",
+            "--reverse",
+            "--threads",
+            "4",
+        ],
+        "tests/expected/sort_name_reverse.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn sort_size_path_d() -> TestResult {
+    run_ordered(
+        &[
+            "tests/inputs/d",
+            "-t",
+            "f",
+            "l",
+            "-n",
+            "^d",
+            "-n",
+            "^b",
+            "--sort",
+            "size",
+        ],
+        "tests/expected/sort_size_path_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_reverse_without_sort() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--reverse"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_all_type_f_path_d() -> TestResult {
+    run(
+        &["tests/inputs/d", "-L", "-t", "f"],
+        "tests/expected/follow_all_type_f_path_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_all_threads_type_f_path_d() -> TestResult {
+    run(
+        &["tests/inputs/d", "-L", "-t", "f", "--threads", "2"],
+        "tests/expected/follow_all_type_f_path_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn follow_args_only_follows_roots() -> TestResult {
+    for threads in [&[][..], &["--threads", "4"]] {
+        Command::cargo_bin(PRG)?
+            .args(["tests/inputs/d/b.csv", "-H", "-t", "f"])
+            .args(threads)
+            .assert()
+            .success()
+            .stdout("tests/inputs/d/b.csv\n");
+
+        Command::cargo_bin(PRG)?
+            .args(["tests/inputs/d/b.csv", "-t", "f"])
+            .args(threads)
+            .assert()
+            .success()
+            .stdout("");
+
+        Command::cargo_bin(PRG)?
+            .args(["tests/inputs/d", "-H", "-L", "-P", "-t", "f"])
+            .args(threads)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("b.csv").not());
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(not(windows))]
+fn gen_link_tree() -> Result<tempfile::TempDir, std::io::Error> {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("a/b"))?;
+    symlink("..", dir.path().join("a/b/up"))?;
+    symlink("nowhere", dir.path().join("a/broken"))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn follow_all_reports_loop() -> TestResult {
+    let dir = gen_link_tree()?;
+    let root = dir.path().join("a");
+    let root = root.to_string_lossy();
+    let expected = format!(
+        "File system loop detected; '{root}/b/up' is part of the same file system loop as '{root}'."
+    );
+
+    for threads in [&[][..], &["--threads", "4"]] {
+        Command::cargo_bin(PRG)?
+            .args([&root, "-L"])
+            .args(threads)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(expected.as_str()));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn type_broken_link() -> TestResult {
+    let dir = gen_link_tree()?;
+    let root = dir.path().join("a");
+    let root = root.to_string_lossy();
+    let expected = format!("{root}/broken\n");
+
+    Command::cargo_bin(PRG)?
+        .args([&root, "-P", "-t", "o"])
+        .assert()
+        .success()
+        .stdout(expected.clone());
+
+    // Following links also runs into the loop in the tree
+    for args in [["-L", "-t", "o"], ["-L", "-t", "l"]] {
+        Command::cargo_bin(PRG)?
+            .arg(root.as_ref())
+            .args(args)
+            .assert()
+            .failure()
+            .stdout(expected.clone());
+    }
+
+    Command::cargo_bin(PRG)?
+        .args([&root, "-t", "l"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("broken").and(predicate::str::contains("up")));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[cfg(not(windows))]
+fn gen_special_tree() -> Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("empty_dir"))?;
+    fs::write(dir.path().join("empty.txt"), "")?;
+    fs::write(dir.path().join("script.sh"), "#!/bin/sh\n")?;
+    fs::set_permissions(
+        dir.path().join("script.sh"),
+        fs::Permissions::from_mode(0o755),
+    )?;
+    std::os::unix::net::UnixListener::bind(dir.path().join("socket"))?;
+    std::process::Command::new("mkfifo")
+        .arg(dir.path().join("fifo"))
+        .status()?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn special_types() -> TestResult {
+    use std::os::unix::fs::FileTypeExt;
+
+    let dir = gen_special_tree()?;
+    let root = dir.path().to_string_lossy();
+
+    for (entry_type, expected) in [
+        ("s", vec!["socket"]),
+        ("p", vec!["fifo"]),
+        ("x", vec!["script.sh"]),
+        ("e", vec!["empty.txt", "empty_dir"]),
+        ("b", vec![]),
+        ("c", vec![]),
+    ] {
+        let cmd = Command::cargo_bin(PRG)?
+            .args([&root, "-t", entry_type])
+            .assert()
+            .success();
+        let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+        let mut lines: Vec<&str> = stdout.lines().collect();
+        lines.sort();
+
+        let expected: Vec<String> = expected
+            .iter()
+            .map(|name| format!("{}/{}", root, name))
+            .collect();
+        assert_eq!(lines, expected, "--type {}", entry_type);
+    }
+
+    // %M shows the type like `ls -l`
+    let mut paths = vec![
+        (format!("{}/socket", root), 's'),
+        (format!("{}/fifo", root), 'p'),
+        ("/dev/null".to_string(), 'c'),
+    ];
+    // Block devices aren't found in every sandbox
+    let block_device = fs::read_dir("/dev")?
+        .filter_map(Result::ok)
+        .find(|entry| entry.file_type().is_ok_and(|kind| kind.is_block_device()));
+    if let Some(entry) = block_device {
+        paths.push((entry.path().to_string_lossy().to_string(), 'b'));
+    }
+
+    for (path, kind) in paths {
+        Command::cargo_bin(PRG)?
+            .args([&path, "--printf", "%M\n"])
+            .assert()
+            .success()
+            .stdout(predicate::str::is_match(format!(
+                "^{}[-rwx]{{9}}\n$",
+                kind
+            ))?);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn type_char_device() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["/dev/null", "-t", "c"])
+        .assert()
+        .success()
+        .stdout("/dev/null\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+fn gen_content_tree() -> Result<tempfile::TempDir, std::io::Error> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("empty_dir"))?;
+    fs::write(
+        dir.path().join("config.yaml"),
+        "user: me\npassword: secret\n",
+    )?;
+    fs::write(dir.path().join("other.yaml"), "user: me\n")?;
+    fs::write(
+        dir.path().join("notes.txt"),
+        "the password is in config.yaml\n",
+    )?;
+    fs::write(dir.path().join("data.bin"), b"\0\0password\0")?;
+    fs::write(dir.path().join("logo"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR")?;
+    fs::write(dir.path().join("empty.txt"), "")?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+fn run_names(args: &[&str], expected: &[&str]) -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let mut names: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| Path::new(line).file_name()?.to_str())
+        .collect();
+    names.sort();
+
+    assert_eq!(names, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn contains() -> TestResult {
+    let dir = gen_content_tree()?;
+    let root = dir.path().to_string_lossy();
+
+    run_names(
+        &[&root, "--contains", "password"],
+        &["config.yaml", "notes.txt"],
+    )?;
+    run_names(
+        &[&root, "-n", "[.]yaml$", "--contains", "^password:"],
+        &["config.yaml"],
+    )?;
+    run_names(
+        &[&root, "--contains", "password", "--threads", "2"],
+        &["config.yaml", "notes.txt"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mime() -> TestResult {
+    let dir = gen_content_tree()?;
+    let root = dir.path().to_string_lossy();
+
+    run_names(&[&root, "--mime", "image/png"], &["logo"])?;
+    run_names(&[&root, "--mime", "image/*"], &["logo"])?;
+    run_names(
+        &[&root, "--mime", "text/plain"],
+        &["config.yaml", "notes.txt", "other.yaml"],
+    )?;
+    run_names(
+        &[
+            &root,
+            "--mime",
+            "application/octet-stream",
+            "--mime",
+            "image/*",
+        ],
+        &["data.bin", "logo"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn empty() -> TestResult {
+    let dir = gen_content_tree()?;
+    let root = dir.path().to_string_lossy();
+
+    run_names(&[&root, "--empty"], &["empty.txt", "empty_dir"])?;
+    run_names(&[&root, "--empty", "-t", "f"], &["empty.txt"])?;
+    run_names(&["tests/inputs", "--empty"], &[])
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates() -> TestResult {
+    run(
+        &["tests/inputs", "--duplicates"],
+        "tests/expected/duplicates.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates_json() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    let prefix = "x".repeat(5000);
+    fs::write(dir.path().join("a.txt"), "same")?;
+    fs::write(dir.path().join("b.txt"), "same")?;
+    fs::write(dir.path().join("c.txt"), "diff")?;
+    fs::write(dir.path().join("big1.dat"), format!("{prefix}1"))?;
+    fs::write(dir.path().join("big2.dat"), format!("{prefix}2"))?;
+    fs::write(dir.path().join("big3.dat"), format!("{prefix}1"))?;
+    fs::write(dir.path().join("empty1.txt"), "")?;
+    fs::write(dir.path().join("empty2.txt"), "")?;
+    let root = dir.path().to_string_lossy();
+
+    let cmd = Command::cargo_bin(PRG)?
+        .args([&root, "--duplicates", "--json"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let sets = stdout
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+
+    let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+    assert_eq!(sets.len(), 2);
+    assert_eq!(sets[1]["size"], 5001);
+    assert_eq!(
+        sets[1]["paths"],
+        serde_json::json!([path("big1.dat"), path("big3.dat")])
+    );
+    assert_eq!(sets[0]["size"], 4);
+    assert_eq!(
+        sets[0]["paths"],
+        serde_json::json!([path("a.txt"), path("b.txt")])
+    );
+    assert_eq!(
+        sets[0]["hash"],
+        "0967115f2813a3541eaef77de9d9d5773f1c0c04314b0bbfe4ff3b3b1c55b5d5"
+    );
+
+    run_names(&[&root, "--duplicates", "-n", "^[ac]"], &[])
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn duplicates_same_file() -> TestResult {
+    // d/b.csv links to a/b/b.csv, so following it finds no second copy
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-n", "b.csv", "-L", "--duplicates"])
+        .assert()
+        .success()
+        .stdout("");
+
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("a.txt"), "same")?;
+    fs::hard_link(dir.path().join("a.txt"), dir.path().join("b.txt"))?;
+    let root = dir.path().to_string_lossy();
+    Command::cargo_bin(PRG)?
+        .args([&root, "--duplicates"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn json() -> TestResult {
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["tests/inputs/d", "--json", "--sort", "name"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let entries = stdout
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|entry| (entry["type"].as_str(), entry["depth"].as_u64()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Some("d"), Some(0)),
+            (Some("l"), Some(1)),
+            (Some("f"), Some(1)),
+            (Some("f"), Some(1)),
+            (Some("d"), Some(1)),
+            (Some("f"), Some(2)),
+        ]
+    );
+
+    assert_eq!(entries[0]["path"], "tests/inputs/d");
+    assert_eq!(entries[1]["target"], "../a/b/b.csv");
+    assert_eq!(entries[2]["size"], 2);
+    assert!(entries[2]["target"].is_null());
+    for field in ["mode", "uid", "gid", "atime", "mtime", "ctime"] {
+        assert!(entries[2].get(field).is_some(), "missing {}", field);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_json_printf() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--json", "--printf", "%p"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+fn gen_ignore_tree(git: bool) -> Result<tempfile::TempDir, std::io::Error> {
+    let dir = tempfile::tempdir()?;
+    if git {
+        fs::create_dir(dir.path().join(".git"))?;
+    }
+    fs::create_dir_all(dir.path().join("src/gen"))?;
+    fs::create_dir_all(dir.path().join("target/debug"))?;
+    fs::write(dir.path().join(".gitignore"), "target/\n*.log\n!keep.log\n")?;
+    fs::write(dir.path().join(".ignore"), "secret.rs\n")?;
+    fs::write(dir.path().join("src/.gitignore"), "gen/\n")?;
+    fs::write(dir.path().join("src/main.rs"), "")?;
+    fs::write(dir.path().join("src/secret.rs"), "")?;
+    fs::write(dir.path().join("src/gen/out.rs"), "")?;
+    fs::write(dir.path().join("src/.hidden.rs"), "")?;
+    fs::write(dir.path().join("target/debug/build.rs"), "")?;
+    fs::write(dir.path().join("debug.log"), "")?;
+    fs::write(dir.path().join("keep.log"), "")?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore() -> TestResult {
+    let dir = gen_ignore_tree(true)?;
+    let root = dir.path().to_string_lossy();
+
+    run_names(
+        &[&root, "-t", "f", "-n", "[.](rs|log)$"],
+        &[
+            ".hidden.rs",
+            "build.rs",
+            "debug.log",
+            "keep.log",
+            "main.rs",
+            "out.rs",
+            "secret.rs",
+        ],
+    )?;
+    for threads in [&[][..], &["--threads", "2"]] {
+        let args = [
+            &[&*root, "-t", "f", "-n", "[.](rs|log)$", "--respect-ignore"],
+            threads,
+        ];
+        run_names(&args.concat(), &["keep.log", "main.rs"])?;
+    }
+    run_names(
+        &[&root, "-n", "[.]rs$", "--respect-ignore", "--hidden"],
+        &[".hidden.rs", "main.rs"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore_outside_repo() -> TestResult {
+    // Only .ignore files apply outside of a git repository
+    let dir = gen_ignore_tree(false)?;
+    let root = dir.path().to_string_lossy();
+
+    run_names(
+        &[&root, "-t", "f", "-n", "[.](rs|log)$", "--respect-ignore"],
+        &["build.rs", "debug.log", "keep.log", "main.rs", "out.rs"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn no_hidden() -> TestResult {
+    let dir = gen_ignore_tree(true)?;
+    let root = dir.path().to_string_lossy();
+
+    run_names(
+        &[&root, "-n", "^[.]", "--no-hidden"],
+        &[dir.path().file_name().unwrap().to_str().unwrap()],
+    )?;
+    run_names(
+        &[&root, "-n", "[.]rs$", "--no-hidden", "--hidden"],
+        &[".hidden.rs", "build.rs", "main.rs", "out.rs", "secret.rs"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(target_os = "linux")]
+fn watch() -> TestResult {
+    use assert_cmd::cargo::CommandCargoExt;
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("a"))?;
+    fs::write(dir.path().join("a/old.txt"), "")?;
+    let root = dir.path().to_string_lossy();
+
+    let mut child = std::process::Command::cargo_bin(PRG)?
+        .args([&root, "--watch", "-n", "[.]txt$"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+            let _ = tx.send(line);
+        }
+    });
+
+    // The watches are added right after the initial search
+    let timeout = Duration::from_secs(5);
+    assert_eq!(rx.recv_timeout(timeout)?, format!("{root}/a/old.txt"));
+    std::thread::sleep(Duration::from_millis(200));
+
+    fs::write(dir.path().join("new.txt"), "new")?;
+    fs::write(dir.path().join("new.rs"), "new")?;
+    fs::write(dir.path().join("new.txt"), "newer")?;
+    fs::remove_file(dir.path().join("a/old.txt"))?;
+
+    // A new file is only reported as created when it is first written, so
+    // the modified line comes from the second write
+    let expected = [
+        format!("created\t{root}/new.txt"),
+        format!("modified\t{root}/new.txt"),
+        format!("deleted\t{root}/a/old.txt"),
+    ];
+    let mut lines = vec![];
+    while lines.len() < expected.len() {
+        match rx.recv_timeout(timeout) {
+            Ok(line) => lines.push(line),
+            Err(_) => break,
+        }
+    }
+    child.kill()?;
+
+    assert_eq!(lines, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn summarize() -> TestResult {
+    run_ordered(
+        &["tests/inputs", "--summarize"],
+        "tests/expected/summarize.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn summarize_depth_size() -> TestResult {
+    run_ordered(
+        &[
+            "tests/inputs",
+            "--summarize",
+            "--summarize-depth",
+            "1",
+            "--sort",
+            "size",
+            "--reverse",
+        ],
+        "tests/expected/summarize_depth_size.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn summarize_human_json() -> TestResult {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("big"))?;
+    fs::write(dir.path().join("big/file"), vec![0; 1536 * 1024])?;
+    fs::write(dir.path().join("small"), "hello")?;
+    let root = dir.path().to_string_lossy();
+    let big = dir.path().join("big");
+    let big = big.to_string_lossy();
+
+    Command::cargo_bin(PRG)?
+        .args([&root, "--summarize", "--human"])
+        .assert()
+        .success()
+        .stdout(format!("1.5M\t2\t{root}\n1.5M\t1\t{big}\n"));
+
+    let small = format!(r#"{{"path":{:?},"size":5,"files":1}}"#, root);
+    Command::cargo_bin(PRG)?
+        .args([&root, "-n", "^small$", "--summarize", "--json"])
+        .assert()
+        .success()
+        .stdout(small + "\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+fn gen_archive_tree() -> Result<tempfile::TempDir, Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir()?;
+    let files: [(&str, &[u8]); 2] = [("pkg/main.rs", b"fn main() {}\n"), ("pkg/README", b"")];
+
+    let mut tar = tar::Builder::new(vec![]);
+    for (path, content) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        tar.append_data(&mut header, path, content)?;
+    }
+    let tar = tar.into_inner()?;
+    fs::write(dir.path().join("bundle.tar"), &tar)?;
+
+    let mut gz = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    gz.write_all(&tar)?;
+    fs::write(dir.path().join("bundle.tar.gz"), gz.finish()?)?;
+
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+    let options =
+        zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    zip.add_directory("pkg/", options)?;
+    for (path, content) in files {
+        zip.start_file(path, options)?;
+        zip.write_all(content)?;
+    }
+    fs::write(dir.path().join("bundle.zip"), zip.finish()?.into_inner())?;
+    fs::write(dir.path().join("main.rs"), "fn main() {}\n")?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives() -> TestResult {
+    let dir = gen_archive_tree()?;
+    let root = dir.path().to_string_lossy();
+    let members = |archive: &str, inner: &[&str]| -> Vec<String> {
+        inner
+            .iter()
+            .map(|inner| format!("{root}/{archive}!/{inner}"))
+            .collect()
+    };
+
+    for threads in [&[][..], &["--threads", "2"]] {
+        let args = [&[&*root, "--search-archives", "--sort", "name"], threads].concat();
+        let mut expected = vec![root.to_string(), format!("{root}/bundle.tar")];
+        expected.extend(members("bundle.tar", &["pkg", "pkg/main.rs", "pkg/README"]));
+        expected.push(format!("{root}/bundle.tar.gz"));
+        expected.extend(members(
+            "bundle.tar.gz",
+            &["pkg", "pkg/main.rs", "pkg/README"],
+        ));
+        expected.push(format!("{root}/bundle.zip"));
+        expected.extend(members("bundle.zip", &["pkg", "pkg/main.rs", "pkg/README"]));
+        expected.push(format!("{root}/main.rs"));
+
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .success()
+            .stdout(expected.join("\n") + "\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives_filters() -> TestResult {
+    let dir = gen_archive_tree()?;
+    let root = dir.path().to_string_lossy();
+
+    run_names(
+        &[&root, "--search-archives", "-n", "[.]rs$", "-t", "f"],
+        &["main.rs", "main.rs", "main.rs", "main.rs"],
+    )?;
+    run_names(
+        &[&root, "--search-archives", "-t", "d", "-n", "pkg"],
+        &["pkg", "pkg", "pkg"],
+    )?;
+    run_names(
+        &[
+            &root,
+            "--search-archives",
+            "--contains",
+            "fn main",
+            "-n",
+            "[.]rs$",
+        ],
+        &["main.rs", "main.rs", "main.rs", "main.rs"],
+    )?;
+
+    let expected = format!("{root}/bundle.tar!/pkg/README\n");
+    Command::cargo_bin(PRG)?
+        .args([
+            &format!("{root}/bundle.tar"),
+            "--search-archives",
+            "--empty",
+        ])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives_not_an_archive() -> TestResult {
+    // A file named like an archive that can't be read as one is reported
+    // like any other unreadable entry, and the search goes on
+    let dir = gen_archive_tree()?;
+    fs::write(dir.path().join("notes.zip"), "not a zip\n")?;
+    let root = dir.path().to_string_lossy();
+
+    Command::cargo_bin(PRG)?
+        .args([&root, "--search-archives", "-n", "[.]zip$|^main"])
+        .args(["--sort", "name"])
+        .assert()
+        .failure()
+        .stdout(format!(
+            "{root}/bundle.tar!/pkg/main.rs\n{root}/bundle.tar.gz!/pkg/main.rs\n\
+            {root}/bundle.zip\n{root}/bundle.zip!/pkg/main.rs\n{root}/main.rs\n{root}/notes.zip\n"
+        ))
+        .stderr(predicate::str::contains(format!("{root}/notes.zip: ")));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn relative_to() -> TestResult {
+    let expected = ["../a", "../a/a.txt", "../a/b", "../a/b/b.csv"].join("\n") + "\n";
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "--relative-to", "tests/inputs/f"])
+        .args(["-n", "^[ab]", "--sort", "name", "--path-style", "posix"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    Command::cargo_bin(PRG)?
+        .args([
+            "tests/inputs/a/b",
+            "-t",
+            "f",
+            "--relative-to",
+            "tests/inputs/a",
+        ])
+        .args(["--printf", "%h %p\\n", "--path-style", "posix"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("b b/b.csv\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn absolute() -> TestResult {
+    let cwd = std::env::current_dir()?;
+    let expected = format!(
+        "{}\n",
+        cwd.join("tests").join("inputs").join("g.csv").display()
+    );
+    Command::cargo_bin(PRG)?
+        .args(["./tests/../tests/inputs/g.csv", "--absolute"])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_absolute_relative_to() -> TestResult {
+    Command::cargo_bin(PRG)?
+        .args(["--absolute", "--relative-to", "tests"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
@@ -0,0 +1,70 @@
+use findr::{EntryType, FindBuilder, FindError, SortKey};
+use regex::Regex;
+use std::path::PathBuf;
+
+type TestResult = Result<(), Box<dyn std::error::Error>>;
+
+// --------------------------------------------------
+#[test]
+fn finds_by_name_and_type() -> TestResult {
+    let mut paths = FindBuilder::new("tests/inputs")
+        .entry_type(EntryType::File)
+        .name(Regex::new(r"\.csv$")?)
+        .build()
+        .map(|found| found.map(|found| found.into_path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    assert_eq!(
+        paths,
+        ["tests/inputs/a/b/b.csv", "tests/inputs/g.csv"].map(PathBuf::from)
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn walks_paths_in_order() -> TestResult {
+    let paths = FindBuilder::new("tests/inputs/f")
+        .path("tests/inputs/a")
+        .sort(SortKey::Name)
+        .build()
+        .map(|found| found.map(|found| found.into_path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    assert_eq!(
+        paths,
+        [
+            "tests/inputs/f",
+            "tests/inputs/f/f.txt",
+            "tests/inputs/a",
+            "tests/inputs/a/a.txt",
+            "tests/inputs/a/b",
+            "tests/inputs/a/b/b.csv",
+            "tests/inputs/a/b/c",
+            "tests/inputs/a/b/c/c.mp3",
+        ]
+        .map(PathBuf::from)
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn yields_errors_and_keeps_going() -> TestResult {
+    let results: Vec<_> = FindBuilder::new("tests/inputs/nope")
+        .path("tests/inputs/g.csv")
+        .build()
+        .collect();
+
+    assert_eq!(results.len(), 2);
+    match &results[0] {
+        Err(err @ FindError::Io { .. }) => {
+            assert_eq!(err.kind(), "not-found");
+            assert_eq!(err.path(), PathBuf::from("tests/inputs/nope"));
+        }
+        other => panic!("expected a not-found error, got {:?}", other),
+    }
+    assert_eq!(results[1].as_ref().map(|found| found.depth()).ok(), Some(0));
+    Ok(())
+}